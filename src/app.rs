@@ -1,32 +1,62 @@
 use crate::solver::orientation::Orientation;
-use crate::solver::token::Token;
+use crate::solver::puzzle_def::PuzzleDef;
 use crate::solver::token::TokenType;
-use crate::solver::LaserMazeSolver;
+use crate::solver::token::{MirrorRules, Token};
+use crate::solver::{
+    beam_hits_laser_side, beam_path, beam_path_until_failure, check_board_with_unplaced, explain,
+    is_fully_oriented, off_board_exit, orientation_is_legal, suggested_targets, targets_bounds,
+    traced_cells, BeamPath, CheckError, LaserMazeSolver, PlacementOrder, SolveEvent,
+};
 
 use eframe::egui;
 use eframe::App;
 
 use serde::{Deserialize, Serialize};
 
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::mpsc::UnboundedReceiver;
+
 mod widgets;
+use eframe::egui::vec2;
 use eframe::egui::Key;
 use eframe::egui::Slider;
 use widgets::cell::collections::Bank;
 use widgets::cell::collections::Grid;
 use widgets::cell::collections::ToBeAdded;
+use widgets::cell::Cell;
 
+mod best_times;
+mod board_import;
 mod challenges;
+#[cfg(test)]
+mod corpus;
+mod grid_index;
 mod menus;
+mod move_log;
 mod resources;
+mod saved_board;
 
+use best_times::{format_time, BestTimes};
+use challenges::Challenges;
+use grid_index::{GuiIndex, ModelIndex};
 use menus::LoadIncludedChallengesMenu;
+use move_log::{MoveKind, MoveRecorder};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Tokens {
     grid: [Option<Token>; 25],
     to_be_added: [Option<Token>; 6],
     bank: [Option<Token>; 11],
     targets: u8,
+    // grid cells the laser must pass through
+    #[serde(default)]
+    waypoints: [bool; 25],
+    // grid cells the laser must not enter
+    #[serde(default)]
+    forbidden: [bool; 25],
 }
 
 impl Default for Tokens {
@@ -50,36 +80,253 @@ impl Default for Tokens {
             to_be_added: Default::default(),
             bank,
             targets: 1,
+            waypoints: Default::default(),
+            forbidden: Default::default(),
+        }
+    }
+}
+
+impl Tokens {
+    // the grid, in the coordinates used by the solver
+    fn model_grid(&self) -> [Option<Token>; 25] {
+        let mut grid: [Option<Token>; 25] = Default::default();
+        for (i, token) in self.grid.iter().enumerate() {
+            grid[GuiIndex::new(i).to_model().get()].clone_from(token);
+        }
+        grid
+    }
+
+    // the waypoint cell indices, in the coordinates used by the solver
+    fn model_waypoints(&self) -> Vec<usize> {
+        (0..25)
+            .filter(|i| self.waypoints[*i])
+            .map(|i| GuiIndex::new(i).to_model().get())
+            .collect()
+    }
+
+    // the forbidden cell indices, in the coordinates used by the solver
+    fn model_forbidden(&self) -> Vec<usize> {
+        (0..25)
+            .filter(|i| self.forbidden[*i])
+            .map(|i| GuiIndex::new(i).to_model().get())
+            .collect()
+    }
+
+    // the tokens to be added, without the empty slots
+    fn to_be_added_tokens(&self) -> Vec<Token> {
+        self.to_be_added.iter().flatten().cloned().collect()
+    }
+
+    // clears the orientation of every token on the grid but the cell blockers, which always face
+    // north; the tokens stay where they are. returns the cells which were oriented
+    fn reset_orientations(&mut self) -> Vec<usize> {
+        let mut result = vec![];
+        for (i, token) in self.grid.iter_mut().enumerate() {
+            if let Some(token) = token {
+                if token.type_() != &TokenType::CellBlocker && token.orientation.is_some() {
+                    token.orientation = None;
+                    result.push(i);
+                }
+            }
         }
+        result
+    }
+
+    // a solver for this board, under the official rules
+    fn solver(&self) -> LaserMazeSolver {
+        LaserMazeSolver::new(self.model_grid(), self.to_be_added_tokens(), self.targets)
+            .with_waypoints(self.model_waypoints())
+            .with_forbidden_cells(self.model_forbidden())
     }
 }
 
+impl From<PuzzleDef> for Tokens {
+    fn from(puzzle_def: PuzzleDef) -> Self {
+        // PuzzleDef uses the solver's cell coordinates
+        let mut grid: [Option<Token>; 25] = Default::default();
+        for (i, token) in puzzle_def.grid.into_iter().enumerate() {
+            grid[ModelIndex::new(i).to_gui().get()] = token;
+        }
+
+        let mut to_be_added: [Option<Token>; 6] = Default::default();
+        for (cell, token) in to_be_added.iter_mut().zip(puzzle_def.to_be_added) {
+            *cell = Some(token);
+        }
+
+        Self {
+            grid,
+            to_be_added,
+            bank: Default::default(),
+            targets: puzzle_def.targets,
+            waypoints: Default::default(),
+            forbidden: Default::default(),
+        }
+    }
+}
+
+// where the theme is kept in eframe's storage
+const DARK_MODE_KEY: &str = "dark_mode";
+
+// how long practice mode shows the solution before putting the board back
+const PRACTICE_FLASH_DURATION: Duration = Duration::from_secs(1);
+// how long a target mirror is highlighted after M toggles whether it must be lit
+const MUST_LIGHT_FLASH_DURATION: Duration = Duration::from_millis(400);
+
+// the cells shrink with the window so the bank and grid always fit, but no further than this
+const MIN_CELL_SIZE: f32 = 40.;
+const MAX_CELL_SIZE: f32 = 100.;
+// room for everything beside the cells: across, the spacing and row labels; down, the headings and
+// the options and buttons below the grid
+const NON_CELL_WIDTH: f32 = 80.;
+const NON_CELL_HEIGHT: f32 = 300.;
+
+// the bank (4 columns) and the grid (5 columns) sit side by side, and the to be added row (0.82 of
+// a cell) sits above the grid's 5 rows
+fn responsive_cell_size(available: egui::Vec2) -> f32 {
+    let from_width = (available.x - NON_CELL_WIDTH) / 9.;
+    let from_height = (available.y - NON_CELL_HEIGHT) / 5.82;
+    from_width
+        .min(from_height)
+        .clamp(MIN_CELL_SIZE, MAX_CELL_SIZE)
+}
+
 pub struct MyApp {
+    // set each frame from the window size
     cell_size: f32,
     tokens: Tokens,
 
     images: resources::ImageBank,
 
     token_move_indices: Option<(usize, usize)>,
+    // keyboard navigation: the selected cell, and the cell of the token picked up with Enter
+    selected_index: Option<usize>,
+    held_index: Option<usize>,
+    // the grid cells shift-clicked to move together; dragging any of them moves them all
+    grouped_cells: Vec<usize>,
 
     message_text: String,
 
     load_included_challenges_menu: LoadIncludedChallengesMenu,
+
+    tint_tokens: bool,
+
+    practice_mode: bool,
+    // label the grid's rows A-E and columns 1-5
+    coordinate_labels: bool,
+    // debugging: paint each grid cell's GUI index and model index
+    index_overlay: bool,
+    // draw the beam across the grid, with a cross where beams intersect
+    beam_overlay: bool,
+    // hint: draw the beam only as far as it gets before it first goes wrong
+    beam_until_failure: bool,
+    // set whenever the tokens change, so what's worked out from the board (like the beam) is only
+    // worked out again when it's out of date
+    dirty: bool,
+    // the beam across the grid, as of the last time the board was dirty; None while a token is
+    // unoriented
+    beam: Option<BeamPath>,
+    // house rule: the double mirror lets the laser straight through instead of reflecting it
+    transparent_double_mirror: bool,
+    // the order the solver tries the empty cells in
+    placement_order: PlacementOrder,
+    // while practice mode shows a solution: the board from before solving, and when to restore it
+    practice_flash: Option<(Tokens, Instant)>,
+    // after M toggles whether a target mirror must be lit: its chained index, and when to stop
+    // highlighting it
+    must_light_flash: Option<(usize, Instant)>,
+
+    // the board from before the last Solve, for "Revert solution"
+    pre_solve_tokens: Option<Tokens>,
+
+    // keep showing the to be added tray after solving, to compare the puzzle with its solution
+    keep_tray: bool,
+    // with keep_tray, after solving: the solved board, and the tray from before solving; the tray is
+    // shown grayed out until the board changes
+    solved_tray: Option<(Tokens, [Option<Token>; 6])>,
+
+    // the last board solved, the mirror rules it was solved with, and its solution in the solver's
+    // coordinates; solving the same board again shows it without searching
+    solution_cache: Option<(Tokens, MirrorRules, [Option<Token>; 25])>,
+
+    // while the solver runs in the background: its events, the board from before solving, and when
+    // it started
+    solving: Option<(UnboundedReceiver<SolveEvent>, Tokens, Instant)>,
+
+    // after Check: the checked board, and the grid cell and direction the beam escaped the board;
+    // the arrow is shown until the board changes
+    beam_exit: Option<(Tokens, usize, Orientation)>,
+    // after Check: the checked board, and its grid as the checker left it (with the lit tokens), for
+    // the tooltips; kept until the board changes
+    checked_cells: Option<(Tokens, [Option<Token>; 25])>,
+
+    // Some while the user has opted in to recording their moves
+    move_recorder: Option<MoveRecorder>,
+
+    // the position in Challenges::iter() of the challenge last loaded with PageUp/PageDown
+    challenge_index: Option<usize>,
+
+    // the light or dark theme, saved between runs
+    dark_mode: bool,
+    // the fastest the user has solved each included challenge by hand, saved between runs
+    best_times: BestTimes,
+    // since an included challenge was loaded: which one, and when; stopped once the board is solved
+    // by hand, and dropped if the solver is used or another board is imported
+    challenge_timer: Option<(Challenges, Instant)>,
 }
 
 impl Default for MyApp {
     fn default() -> Self {
         Self {
-            cell_size: 100.,
+            cell_size: MAX_CELL_SIZE,
             tokens: Default::default(),
             images: Default::default(),
             token_move_indices: Default::default(),
+            selected_index: None,
+            held_index: None,
+            grouped_cells: vec![],
             message_text: Default::default(),
             load_included_challenges_menu: Default::default(),
+            tint_tokens: false,
+            practice_mode: false,
+            coordinate_labels: false,
+            index_overlay: false,
+            beam_overlay: false,
+            beam_until_failure: false,
+            dirty: true,
+            beam: None,
+            transparent_double_mirror: false,
+            placement_order: PlacementOrder::default(),
+            practice_flash: None,
+            must_light_flash: None,
+            pre_solve_tokens: None,
+            keep_tray: false,
+            solved_tray: None,
+            solution_cache: None,
+            solving: None,
+            move_recorder: None,
+            beam_exit: None,
+            checked_cells: None,
+            challenge_index: None,
+            dark_mode: true,
+            best_times: Default::default(),
+            challenge_timer: None,
         }
     }
 }
 
+impl MyApp {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+        if let Some(storage) = cc.storage {
+            app.dark_mode = eframe::get_value(storage, DARK_MODE_KEY).unwrap_or(true);
+            app.best_times =
+                eframe::get_value(storage, best_times::STORAGE_KEY).unwrap_or_default();
+        }
+        cc.egui_ctx.set_visuals(theme_visuals(app.dark_mode));
+        app
+    }
+}
+
 impl App for MyApp {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
         // responses don't have a default value, and the closure is in its own scope,
@@ -87,6 +334,34 @@ impl App for MyApp {
         let mut bank_responses = None;
         let mut grid_responses = None;
         let mut to_be_added_responses = None;
+
+        // in practice mode, put the board back once the solution has been shown
+        if let Some((_, flash_end)) = &self.practice_flash {
+            if Instant::now() >= *flash_end {
+                let (pre_solve_tokens, _) = self
+                    .practice_flash
+                    .take()
+                    .expect("We just validated that the solution is being shown");
+                self.tokens = pre_solve_tokens;
+                self.dirty = true;
+                self.message_text = "Now try to reproduce the solution!".into();
+            }
+        }
+        let flashing = self.practice_flash.is_some();
+
+        self.poll_solver(ctx);
+        let solving = self.solving.is_some();
+
+        if self.dirty {
+            self.beam = if self.beam_until_failure {
+                beam_path_until_failure(&self.tokens.model_grid(), &self.mirror_rules()).ok()
+            } else {
+                beam_path(&self.tokens.model_grid(), &self.mirror_rules()).ok()
+            };
+            self.stop_challenge_timer_if_solved();
+            self.dirty = false;
+        }
+
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("Challenges", |ui| {
@@ -97,74 +372,292 @@ impl App for MyApp {
                     self.load_included_challenges_menu.open = true;
                     ui.close_menu();
                 });
+                let theme = if self.dark_mode {
+                    "Light theme"
+                } else {
+                    "Dark theme"
+                };
+                if ui.button(theme).clicked() {
+                    self.dark_mode = !self.dark_mode;
+                    ctx.set_visuals(theme_visuals(self.dark_mode));
+                }
             });
         });
         egui::CentralPanel::default().show(ctx, |ui| {
+            // ignore input while practice mode is showing the solution, or the solver is running
+            ui.set_enabled(!flashing && !solving);
+            self.cell_size = responsive_cell_size(ui.available_size());
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
                     ui.heading("Bank");
                     bank_responses =
-                        Some(Bank::new(self.cell_size).show(ui, &self.images, &self.tokens.bank));
+                        Some(Bank::new(self.cell_size, self.tint_tokens).show(ui, &self.images, &self.tokens.bank, local_index(self.selected_index, 25..36), local_index(self.held_index, 25..36)));
                     ui.heading("Controls");
                     ui.label("Mouse drag/drop: Move token");
+                    ui.label("Shift+click: Select grid tokens to drag together");
                     ui.label("W/A/S/D: Reorient hovered token");
+                    ui.label("Scroll wheel: Turn hovered token");
+                    ui.label("Right click: Pick an orientation for the token");
                     ui.label("R: Set hovered token's orientation to unknown");
                     ui.label("M: Toggle whether hovered token must be lit (purple tokens only)");
+                    ui.label("P: Toggle whether the laser must pass through the hovered grid cell");
+                    ui.label("X: Toggle whether the laser must stay out of the hovered grid cell");
+                    ui.label("Arrow keys: Select a cell (W/A/S/D/R/M/P/X apply to it when nothing is hovered)");
+                    ui.label("Enter: Pick up the selected token, or drop it on the selected cell");
+                    ui.label("Esc: Put down the picked up token, and clear the shift+click selection");
+                    ui.label("PageDown/PageUp: Load the next/previous included challenge");
+                    ui.label("Drop a PNG of the board, cropped to the grid: Import the board");
                     ui.heading("Links");
                     ui.hyperlink_to("Game Instructions", "https://www.thinkfun.com/wp-content/uploads/2013/09/Laser-1014-Instructions.pdf");
                     ui.hyperlink_to("Bonus Challenges", "https://www.thinkfun.com/bonus/laser-maze/");
                 });
                 ui.vertical(|ui| {
                     ui.heading("To Be Added");
-                    to_be_added_responses = Some(ToBeAdded::new(self.cell_size * 0.82).show(
-                        ui,
-                        &self.images,
-                        &self.tokens.to_be_added,
-                    ));
+                    // every token to be added must be on the grid for the maze to be solved
+                    match self.tokens.to_be_added_tokens().len() {
+                        0 => ui.label("All tokens placed"),
+                        unplaced => ui.label(format!("{} token(s) left to place", unplaced)),
+                    };
+                    let kept_tray = self.solved_tray.as_ref().filter(|(solved_tokens, _)| *solved_tokens == self.tokens).map(|(_, tray)| tray);
+                    to_be_added_responses = Some(match kept_tray {
+                        // only there to compare with, so it can't be used
+                        Some(tray) => ui.add_enabled_ui(false, |ui| ToBeAdded::new(self.cell_size * 0.82, self.tint_tokens).show(ui, &self.images, tray, None, None)).inner,
+                        None => ToBeAdded::new(self.cell_size * 0.82, self.tint_tokens).show(
+                            ui,
+                            &self.images,
+                            &self.tokens.to_be_added,
+                            local_index(self.selected_index, 36..42),
+                            local_index(self.held_index, 36..42),
+                        ),
+                    });
                     ui.heading("Grid");
                     grid_responses =
-                        Some(Grid::new(self.cell_size).show(ui, &self.images, &self.tokens.grid));
+                        Some(Grid::new(self.cell_size, self.tint_tokens).coordinate_labels(self.coordinate_labels).checked_cells(self.checked_cells.as_ref().filter(|(checked_tokens, _)| *checked_tokens == self.tokens).map(|(_, cells)| cells.clone())).forbidden(self.tokens.forbidden).show(ui, &self.images, &self.tokens.grid, &self.tokens.waypoints, local_index(self.selected_index, 0..25), local_index(self.held_index, 0..25)));
+                    for cell_index in &self.grouped_cells {
+                        let rect = grid_responses.as_ref().unwrap()[*cell_index].rect;
+                        ui.painter().rect_stroke(rect.shrink(rect.width() * 0.12), 0.0, egui::Stroke::new(rect.width() * 0.04, egui::Color32::from_rgb(0, 114, 178)));
+                    }
+                    if self.index_overlay {
+                        for (cell_index, response) in grid_responses.as_ref().unwrap().iter().enumerate() {
+                            Self::paint_cell_indices(ui, response.rect, cell_index);
+                        }
+                    }
+                    if self.beam_overlay {
+                        // nothing to draw until every token on the board is oriented
+                        if let Some(path) = &self.beam {
+                            Self::paint_beam_path(ui, grid_responses.as_ref().unwrap(), path);
+                        }
+                    }
+                    if let Some((checked_tokens, cell_index, orientation)) = &self.beam_exit {
+                        if *checked_tokens == self.tokens {
+                            let rect = grid_responses.as_ref().unwrap()[*cell_index].rect;
+                            Self::paint_beam_exit(ui, rect, orientation);
+                        }
+                    }
                 });
             });
             ui.horizontal(|ui| {
                 ui.label("Number of Targets:");
-                ui.add(Slider::new(&mut self.tokens.targets, 1..=3));
+                // no more targets than there are target mirrors in play
+                let (_, most) = targets_bounds(&self.tokens.model_grid(), &self.tokens.to_be_added_tokens());
+                let most = most.max(1);
+                if self.tokens.targets > most {
+                    self.tokens.targets = most;
+                    self.dirty = true;
+                }
+                if ui.add(Slider::new(&mut self.tokens.targets, 1..=most)).changed() {
+                    self.dirty = true;
+                }
+                if let Some(hint) = self.targets_hint() {
+                    ui.label(hint);
+                }
             });
+            ui.checkbox(&mut self.tint_tokens, "Tint tokens by type and outline targets which must be lit");
+            ui.checkbox(&mut self.practice_mode, "Practice mode: briefly show the solution, then reset the board");
+            ui.checkbox(&mut self.keep_tray, "Keep the to be added tray after solving, to compare");
+            ui.checkbox(&mut self.coordinate_labels, "Label grid rows A-E and columns 1-5");
+            ui.checkbox(&mut self.beam_overlay, "Show the beam");
+            if ui.add_enabled(self.beam_overlay, egui::Checkbox::new(&mut self.beam_until_failure, "Hint: show the beam only until it goes wrong")).changed() {
+                self.dirty = true;
+            }
+            ui.checkbox(&mut self.index_overlay, "Debug: show each grid cell's GUI index (top left) and model index (bottom right)");
+            if ui.checkbox(&mut self.transparent_double_mirror, "House rule: the double mirror is transparent").changed() {
+                // the beam takes a different route under the other rules
+                self.dirty = true;
+            }
+            egui::ComboBox::from_label("Solver: order to try the empty cells in")
+                .selected_text(placement_order_label(self.placement_order))
+                .show_ui(ui, |ui| {
+                    for placement_order in [PlacementOrder::Spiral, PlacementOrder::RowMajor, PlacementOrder::CenterOut] {
+                        ui.selectable_value(&mut self.placement_order, placement_order, placement_order_label(placement_order));
+                    }
+                });
             if ui.button("Print to console").clicked() {
                 self.print_tokens_to_console();
             }
-            if ui.button("Check").clicked() {
-                if self.check() {
-                    self.message_text = "This laser maze is solved!".into()
+            let mut recording = self.move_recorder.is_some();
+            if ui.checkbox(&mut recording, "Record moves").changed() {
+                self.move_recorder = recording.then(MoveRecorder::default);
+            }
+            if ui.add_enabled(self.move_recorder.is_some(), egui::Button::new("Print moves to console")).clicked() {
+                self.print_moves_to_console();
+            }
+            if ui.button("Validate").clicked() {
+                let problems = self.validate();
+                self.message_text = if problems.is_empty() {
+                    "This laser maze is a valid puzzle.".into()
                 } else {
-                    self.message_text = "This laser maze is not solved.".into()
+                    invalid_message(&problems)
+                };
+            }
+            // the checker can't trace the beam through an unoriented token
+            if ui.add_enabled(is_fully_oriented(&self.tokens.grid), egui::Button::new("Check")).on_disabled_hover_text("Every token on the grid needs an orientation first").clicked() {
+                self.record_checked_cells();
+                match self.check() {
+                    Ok(true) => self.message_text = "This laser maze is solved!".into(),
+                    Ok(false) => {
+                        self.beam_exit = off_board_exit(&self.tokens.model_grid(), &self.mirror_rules()).map(|(cell_index, orientation)| {
+                            (self.tokens.clone(), ModelIndex::new(cell_index).to_gui().get(), orientation)
+                        });
+                        let unplaced = self.tokens.to_be_added_tokens().len();
+                        self.message_text = match &self.beam_exit {
+                            None if unplaced > 0 && self.lit_ignoring_unplaced() => {
+                                format!("This laser maze is not solved: the targets are lit, but {} token(s) are still to be added.", unplaced)
+                            }
+                            Some(_) => "This laser maze is not solved: the beam escapes the board where the arrow points.".into(),
+                            None if beam_hits_laser_side(&self.tokens.model_grid(), &self.mirror_rules()) => {
+                                "This laser maze is not solved: the beam comes back into the side of the laser.".into()
+                            }
+                            None => "This laser maze is not solved.".into(),
+                        };
+                        if let Some(hint) = self.remaining_tokens_hint() {
+                            self.message_text = format!("{} {}", self.message_text, hint);
+                        }
+                    }
+                    Err(e) => self.message_text = format!("This laser maze can't be checked: {}", e),
                 }
             }
+            if ui.button("Explain beam").clicked() {
+                let grid = self.tokens.model_grid();
+                self.message_text = match beam_path(&grid, &self.mirror_rules()) {
+                    Ok(path) => explain(&grid, &path.steps, &self.mirror_rules()).join("\n"),
+                    Err(e) => format!("The beam can't be explained: {}", e),
+                };
+            }
             if ui.button("Solve").clicked() {
-                match self.solve() {
-                    Ok(true) => {self.message_text = "Here's the solution!".into()},
-                    Ok(false) => {self.message_text = "This laser maze is not solvable!".into()}
-                    Err(s) => {self.message_text = format!("Error while running solver: {}", s)}
+                self.start_solving(ctx);
+                ctx.request_repaint();
+            }
+            if ui.add_enabled(self.pre_solve_tokens.is_some(), egui::Button::new("Revert solution")).clicked() {
+                if let Some(pre_solve_tokens) = self.pre_solve_tokens.take() {
+                    self.tokens = pre_solve_tokens;
+                    self.dirty = true;
+                    self.message_text = "Restored the board from before solving.".into();
                 }
             }
+            if ui.button("Reset all orientations").clicked() {
+                let cells = self.tokens.reset_orientations();
+                for cell in cells.iter() {
+                    self.record_move(MoveKind::Orient { cell: *cell, orientation: None });
+                }
+                self.dirty = true;
+                self.message_text = format!("Cleared the orientation of {} tokens.", cells.len());
+            }
             ui.label(format!("Message: {}", self.message_text));
         });
 
+        if flashing || solving {
+            self.token_move_indices = None;
+            return;
+        }
+
+        // dropping a picture of the board on the window imports it
+        if let Some(file) = ctx.input(|i| i.raw.dropped_files.first().cloned()) {
+            self.import_board_image(file);
+        }
+
         self.handle_moving_tokens(
             ctx,
             grid_responses.as_ref().unwrap(),
             bank_responses.as_ref().unwrap(),
             to_be_added_responses.as_ref().unwrap(),
         );
+        self.handle_keyboard_navigation(
+            ctx,
+            grid_responses.as_ref().unwrap(),
+            bank_responses.as_ref().unwrap(),
+            to_be_added_responses.as_ref().unwrap(),
+        );
         self.handle_orientation_shortcuts(
             ctx,
             grid_responses.as_ref().unwrap(),
             bank_responses.as_ref().unwrap(),
             to_be_added_responses.as_ref().unwrap(),
         );
-        self.load_included_challenges_menu
-            .show(ctx, &mut self.tokens);
+        self.paint_must_light_flash(
+            ctx,
+            grid_responses.as_ref().unwrap(),
+            bank_responses.as_ref().unwrap(),
+            to_be_added_responses.as_ref().unwrap(),
+        );
+        self.handle_challenge_shortcuts(ctx);
+        self.handle_orientation_context_menus(
+            grid_responses.as_ref().unwrap(),
+            bank_responses.as_ref().unwrap(),
+        );
+        if self
+            .load_included_challenges_menu
+            .show(ctx, &mut self.tokens, &self.best_times)
+        {
+            self.challenge_loaded(self.load_included_challenges_menu.selected_challenge());
+        }
+
+        // the board changed after it was drawn; draw it again with everything worked out afresh
+        if self.dirty {
+            ctx.request_repaint();
+        }
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, DARK_MODE_KEY, &self.dark_mode);
+        eframe::set_value(storage, best_times::STORAGE_KEY, &self.best_times);
+    }
+}
+
+// how the placement orders are named in the solver setting
+fn placement_order_label(placement_order: PlacementOrder) -> &'static str {
+    match placement_order {
+        PlacementOrder::Spiral => "Spiral, from the edge in",
+        PlacementOrder::RowMajor => "Row by row, from the bottom",
+        PlacementOrder::CenterOut => "From the center out",
+    }
+}
+
+// the cell images are opaque and the tokens are drawn on them, so the board reads the same in
+// either theme
+fn theme_visuals(dark_mode: bool) -> egui::Visuals {
+    if dark_mode {
+        egui::Visuals::dark()
+    } else {
+        egui::Visuals::light()
+    }
+}
+
+// the message for an invalid board, with each problem on its own bulleted line
+fn invalid_message(problems: &[String]) -> String {
+    let bullets: Vec<String> = problems
+        .iter()
+        .map(|problem| format!("• {}", problem))
+        .collect();
+    format!("This laser maze is invalid:\n{}", bullets.join("\n"))
+}
+
+// converts an index of the grid, bank and to be added cells chained together into an index of
+// the cell collection spanning `range`, if it's in that collection
+fn local_index(index: Option<usize>, range: Range<usize>) -> Option<usize> {
+    index
+        .filter(|idx| range.contains(idx))
+        .map(|idx| idx - range.start)
 }
 
 impl MyApp {
@@ -180,6 +673,27 @@ impl MyApp {
         // store the indices of the moved tokens from last frame, before we overwrite them
         let last_frame_token_move_indices = self.token_move_indices;
 
+        // shift+click adds a grid token to the group, or takes it out. the tokens may have been moved
+        // or cleared since they were grouped
+        self.grouped_cells
+            .retain(|cell_index| self.tokens.grid[*cell_index].is_some());
+        if ctx.input(|i| i.modifiers.shift) {
+            if let Some(cell_index) = grid_responses
+                .iter()
+                .position(|response| response.clicked())
+            {
+                match self.grouped_cells.iter().position(|i| *i == cell_index) {
+                    Some(position) => {
+                        self.grouped_cells.remove(position);
+                    }
+                    None => self.grouped_cells.push(cell_index),
+                }
+            }
+        }
+        if ctx.input(|i| i.key_pressed(Key::Escape)) {
+            self.grouped_cells.clear();
+        }
+
         // we chan the iterators for the response arrays of the different token repositories, and then enumerate
         // the chained iterator. the order of the chaining will be important to keep in mind later.
 
@@ -216,53 +730,187 @@ impl MyApp {
         // chained iterators above, enumerated after chaining
         if let Some((dragged_index, hovered_index)) = last_frame_token_move_indices {
             if ctx.input(|i| i.pointer.primary_released()) {
-                // clone the token and set its original position to None
-                let moving_token = match dragged_index {
-                    0..=24 => {
-                        let moving_token = self.tokens.grid[dragged_index]
-                            .as_ref()
-                            .expect("We can only drag cells which have a token")
-                            .clone();
-                        self.tokens.grid[dragged_index] = None;
-                        moving_token
-                    }
-                    25..=35 => {
-                        let moving_token = self.tokens.bank[dragged_index - 25]
-                            .as_ref()
-                            .expect("We can only drag cells which have a token")
-                            .clone();
-                        self.tokens.bank[dragged_index - 25] = None;
-                        moving_token
-                    }
-                    36..=41 => {
-                        let moving_token = self.tokens.to_be_added[dragged_index - 36]
-                            .as_ref()
-                            .expect("We can only drag cells which have a token")
-                            .clone();
-                        self.tokens.to_be_added[dragged_index - 36] = None;
-                        moving_token
-                    }
-                    _ => {
-                        panic!("impossible case because of fixed array lengths")
-                    }
-                };
-                // move the cloned token into its new place
-                match hovered_index {
-                    0..=24 => {
-                        self.tokens.grid[hovered_index] = Some(moving_token);
-                    }
-                    25..=35 => {
-                        self.tokens.bank[hovered_index - 25] = Some(moving_token);
-                    }
-                    36..=41 => {
-                        self.tokens.to_be_added[hovered_index - 36] = Some(moving_token);
+                if self.grouped_cells.len() > 1 && self.grouped_cells.contains(&dragged_index) {
+                    self.move_group(dragged_index, hovered_index);
+                } else {
+                    self.move_token(dragged_index, hovered_index);
+                }
+            }
+        }
+    }
+
+    // the cell at `index` of the grid, bank and to be added cells chained together, as in the
+    // handle_* functions
+    fn cell_mut(&mut self, index: usize) -> &mut Option<Token> {
+        match index {
+            0..=24 => &mut self.tokens.grid[index],
+            25..=35 => &mut self.tokens.bank[index - 25],
+            36..=41 => &mut self.tokens.to_be_added[index - 36],
+            _ => {
+                panic!("impossible case because of fixed array lengths")
+            }
+        }
+    }
+
+    /// Put `token` in grid cell `model_index`, replacing any token there. Lets a frontend with its own
+    /// palette of tokens, or a script, set up the board
+    pub fn place_token(&mut self, model_index: ModelIndex, token: Token) {
+        self.tokens.grid[model_index.to_gui().get()] = Some(token);
+        self.dirty = true;
+    }
+
+    /// Take the token out of grid cell `model_index`, if there is one
+    pub fn remove_token(&mut self, model_index: ModelIndex) -> Option<Token> {
+        self.dirty = true;
+        self.tokens.grid[model_index.to_gui().get()].take()
+    }
+
+    // move the token at chained index `from` to chained index `to`
+    fn move_token(&mut self, from: usize, to: usize) {
+        let moving_token = match from {
+            0..=24 => self.remove_token(GuiIndex::new(from).to_model()),
+            _ => self.cell_mut(from).take(),
+        }
+        .expect("We can only move cells which have a token");
+        match to {
+            0..=24 => self.place_token(GuiIndex::new(to).to_model(), moving_token),
+            _ => *self.cell_mut(to) = Some(moving_token),
+        }
+        self.dirty = true;
+        self.record_move(MoveKind::Move { from, to });
+    }
+
+    // move every grouped token by the offset from grid cell `from` to chained index `to`. refused,
+    // with a message, if a token would go off the grid or onto a token outside the group
+    fn move_group(&mut self, from: usize, to: usize) {
+        if to >= 25 {
+            self.message_text =
+                "Shift+click selected tokens can only be moved around the grid.".into();
+            return;
+        }
+        let (row_offset, column_offset) = (
+            (to / 5) as i32 - (from / 5) as i32,
+            (to % 5) as i32 - (from % 5) as i32,
+        );
+        let mut moves = vec![];
+        for cell_index in &self.grouped_cells {
+            let (row, column) = (
+                (cell_index / 5) as i32 + row_offset,
+                (cell_index % 5) as i32 + column_offset,
+            );
+            if !(0..5).contains(&row) || !(0..5).contains(&column) {
+                self.message_text =
+                    "Can't move the selected tokens: some would go off the grid.".into();
+                return;
+            }
+            let destination = (row * 5 + column) as usize;
+            if self.tokens.grid[destination].is_some() && !self.grouped_cells.contains(&destination)
+            {
+                self.message_text =
+                    "Can't move the selected tokens: some would land on another token.".into();
+                return;
+            }
+            moves.push((*cell_index, destination));
+        }
+        // move the tokens furthest along the way first, so each lands in a cell that's already empty
+        moves.sort_by_key(|(cell_index, _)| {
+            -((cell_index / 5) as i32 * row_offset + (cell_index % 5) as i32 * column_offset)
+        });
+        for (cell_index, destination) in &moves {
+            self.move_token(*cell_index, *destination);
+        }
+        self.grouped_cells = moves
+            .into_iter()
+            .map(|(_, destination)| destination)
+            .collect();
+    }
+
+    fn record_move(&mut self, kind: MoveKind) {
+        if let Some(move_recorder) = self.move_recorder.as_mut() {
+            move_recorder.record(kind);
+        }
+    }
+
+    // the arrow keys move the selected cell to the nearest cell on screen in that direction;
+    // Enter picks up the selected token, then drops it on the selected cell if it's empty
+    fn handle_keyboard_navigation(
+        &mut self,
+        ctx: &eframe::egui::Context,
+        grid_responses: &[eframe::egui::Response; 25],
+        bank_responses: &[eframe::egui::Response; 11],
+        to_be_added_responses: &[eframe::egui::Response; 6],
+    ) {
+        // the held token may have been dragged away with the mouse, or a challenge loaded
+        if let Some(held_index) = self.held_index {
+            if self.cell_mut(held_index).is_none() {
+                self.held_index = None;
+            }
+        }
+
+        let direction = ctx.input(|i| {
+            if i.key_pressed(Key::ArrowUp) {
+                Some(vec2(0., -1.))
+            } else if i.key_pressed(Key::ArrowDown) {
+                Some(vec2(0., 1.))
+            } else if i.key_pressed(Key::ArrowLeft) {
+                Some(vec2(-1., 0.))
+            } else if i.key_pressed(Key::ArrowRight) {
+                Some(vec2(1., 0.))
+            } else {
+                None
+            }
+        });
+        if let Some(direction) = direction {
+            let centers = grid_responses
+                .iter()
+                .chain(bank_responses.iter())
+                .chain(to_be_added_responses.iter())
+                .map(|response| response.rect.center())
+                .collect::<Vec<_>>();
+            self.selected_index = match self.selected_index {
+                // start in the bottom left of the grid, where the model's cell 0 is
+                None => Some(ModelIndex::new(0).to_gui().get()),
+                Some(selected_index) => {
+                    let from = centers[selected_index];
+                    // prefer cells straight ahead over cells off to the side
+                    let nearest = centers
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(idx, center)| {
+                            let offset = *center - from;
+                            let ahead = offset.dot(direction);
+                            let aside = (offset - direction * ahead).length();
+                            (ahead > 1.).then_some((idx, ahead + 2. * aside))
+                        })
+                        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                        .map(|(idx, _)| idx);
+                    nearest.or(Some(selected_index))
+                }
+            };
+        }
+
+        if let Some(selected_index) = self.selected_index {
+            if ctx.input(|i| i.key_pressed(Key::Enter)) {
+                match self.held_index {
+                    None => {
+                        if self.cell_mut(selected_index).is_some() {
+                            self.held_index = Some(selected_index);
+                        }
                     }
-                    _ => {
-                        panic!("impossible case because of fixed array lengths")
+                    Some(held_index) => {
+                        if held_index == selected_index {
+                            self.held_index = None;
+                        } else if self.cell_mut(selected_index).is_none() {
+                            self.move_token(held_index, selected_index);
+                            self.held_index = None;
+                        }
                     }
                 }
             }
         }
+        if ctx.input(|i| i.key_pressed(Key::Escape)) {
+            self.held_index = None;
+        }
     }
 
     fn handle_orientation_shortcuts(
@@ -275,107 +923,641 @@ impl MyApp {
         // get the response that is hovered
         // cells with None Token may have hover Sense, but not Dragged Sense; this
         // prevents use from short circuiting find() from the Cell we are dragging
-        if let Some((hovered_index, _)) = grid_responses
+        // without a hovered cell, the shortcuts apply to the cell selected with the keyboard
+        let mouse_hovered_index = grid_responses
             .iter()
             .chain(bank_responses.iter())
             .chain(to_be_added_responses.iter())
             .enumerate()
             .find(|(_idx, response)| response.hovered())
-        {
-            if let Some(token) = match hovered_index {
-                0..=24 => self.tokens.grid[hovered_index].as_mut(),
-                25..=35 => self.tokens.bank[hovered_index - 25].as_mut(),
-                36..=41 => self.tokens.to_be_added[hovered_index - 36].as_mut(),
-                _ => {
-                    panic!("impossible case because of fixed array lengths")
+            .map(|(idx, _)| idx);
+        if let Some(hovered_index) = mouse_hovered_index.or(self.selected_index) {
+            if hovered_index < 25 && ctx.input(|i| i.key_pressed(Key::P)) {
+                self.tokens.waypoints[hovered_index] = !self.tokens.waypoints[hovered_index];
+                // a cell can't be both a waypoint and forbidden
+                self.tokens.forbidden[hovered_index] = false;
+                self.dirty = true;
+            }
+            if hovered_index < 25 && ctx.input(|i| i.key_pressed(Key::X)) {
+                self.tokens.forbidden[hovered_index] = !self.tokens.forbidden[hovered_index];
+                self.tokens.waypoints[hovered_index] = false;
+                self.dirty = true;
+            }
+            let mut orientation = if ctx.input(|i| i.key_pressed(Key::W)) {
+                Some(Some(Orientation::North))
+            } else if ctx.input(|i| i.key_pressed(Key::D)) {
+                Some(Some(Orientation::East))
+            } else if ctx.input(|i| i.key_pressed(Key::S)) {
+                Some(Some(Orientation::South))
+            } else if ctx.input(|i| i.key_pressed(Key::A)) {
+                Some(Some(Orientation::West))
+            } else if ctx.input(|i| i.key_pressed(Key::R)) {
+                Some(None)
+            } else {
+                // the scroll wheel turns the token under the mouse
+                let scroll = ctx.input(|i| i.scroll_delta.y);
+                if mouse_hovered_index.is_some() && scroll != 0. {
+                    self.scrolled_orientation(hovered_index, scroll).map(Some)
+                } else {
+                    None
                 }
-            } {
-                if ctx.input(|i| i.key_pressed(Key::W)) {
-                    token.orientation = Some(Orientation::North);
-                } else if ctx.input(|i| i.key_pressed(Key::D)) {
-                    token.orientation = Some(Orientation::East);
-                } else if ctx.input(|i| i.key_pressed(Key::S)) {
-                    token.orientation = Some(Orientation::South);
-                } else if ctx.input(|i| i.key_pressed(Key::A)) {
-                    token.orientation = Some(Orientation::West);
-                } else if ctx.input(|i| i.key_pressed(Key::R)) {
-                    token.orientation = None;
+            };
+            // refuse orientations pointing off the board where the rules forbid it
+            if let Some(Some(new_orientation)) = &orientation {
+                if hovered_index < 25
+                    && !orientation_is_legal(
+                        &self.tokens.model_grid(),
+                        GuiIndex::new(hovered_index).to_model().get(),
+                        new_orientation,
+                    )
+                {
+                    self.message_text = "That token can't point off the board there!".into();
+                    orientation = None;
+                }
+            }
+            let mut move_kind = None;
+            let mut not_a_target = false;
+            if let Some(token) = self.cell_mut(hovered_index).as_mut() {
+                if let Some(orientation) = orientation {
+                    token.orientation = orientation.clone();
+                    move_kind = Some(MoveKind::Orient {
+                        cell: hovered_index,
+                        orientation,
+                    });
                 } else if ctx.input(|i| i.key_pressed(Key::M)) {
-                    token.toggle_must_light();
+                    // toggle_must_light ignores everything but target mirrors, so say why nothing
+                    // happened
+                    if token.type_() == &TokenType::TargetMirror {
+                        token.toggle_must_light();
+                        move_kind = Some(MoveKind::ToggleMustLight {
+                            cell: hovered_index,
+                        });
+                    } else {
+                        not_a_target = true;
+                    }
                 }
             }
+            if not_a_target {
+                self.message_text = "Only a target mirror can be marked as must light!".into();
+            }
+            if let Some(MoveKind::ToggleMustLight { cell }) = &move_kind {
+                self.must_light_flash = Some((*cell, Instant::now() + MUST_LIGHT_FLASH_DURATION));
+            }
+            if let Some(move_kind) = move_kind {
+                self.dirty = true;
+                self.record_move(move_kind);
+            }
+        }
+    }
+
+    // the orientation after turning the token at chained index `index` one step through the
+    // orientations it may take in its cell: clockwise when scrolling down, counterclockwise when
+    // scrolling up. None if there's no token, or it only has one orientation
+    fn scrolled_orientation(&self, index: usize, scroll: f32) -> Option<Orientation> {
+        let token = match index {
+            0..=24 => self.tokens.grid[index].as_ref(),
+            25..=35 => self.tokens.bank[index - 25].as_ref(),
+            _ => self.tokens.to_be_added[index - 36].as_ref(),
+        }?;
+        let orientation_indices = if index < 25 {
+            self.generate_solver()
+                .stack
+                .pop()
+                .expect("LaserMazeSolver initializes with a node")
+                .orientation_iter(token.type_(), GuiIndex::new(index).to_model().get())
+        } else {
+            // off the grid, only the token's symmetry matters
+            token.type_().orientation_range()
+        };
+        if orientation_indices.len() < 2 {
+            return None;
+        }
+
+        let n = orientation_indices.len();
+        // a symmetric token facing e.g. South is in the same position as North
+        let symmetry = token.type_().orientation_range().len();
+        let position = token.orientation().and_then(|orientation| {
+            orientation_indices
+                .iter()
+                .position(|i| *i == orientation.to_index() % symmetry)
+        });
+        // egui's scroll delta is positive when scrolling up
+        let new_position = match (position, scroll < 0.) {
+            (Some(position), true) => (position + 1) % n,
+            (Some(position), false) => (position + n - 1) % n,
+            (None, true) => 0,
+            (None, false) => n - 1,
+        };
+        Some(Orientation::from_index(orientation_indices[new_position]))
+    }
+
+    // PageDown and PageUp load the next and previous included challenge, wrapping around. like the
+    // menu, they ask before discarding an edited board
+    fn handle_challenge_shortcuts(&mut self, ctx: &eframe::egui::Context) {
+        // the keys belong to a text field which has focus
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let n_challenges = Challenges::iter().len();
+        let challenge_index = if ctx.input(|i| i.key_pressed(Key::PageDown)) {
+            match self.challenge_index {
+                Some(i) => (i + 1) % n_challenges,
+                None => 0,
+            }
+        } else if ctx.input(|i| i.key_pressed(Key::PageUp)) {
+            match self.challenge_index {
+                Some(i) => (i + n_challenges - 1) % n_challenges,
+                None => n_challenges - 1,
+            }
+        } else {
+            return;
+        };
+        let challenge = *Challenges::iter()
+            .nth(challenge_index)
+            .expect("We just wrapped the index into range");
+        if self
+            .load_included_challenges_menu
+            .load(challenge, &mut self.tokens)
+        {
+            self.challenge_loaded(challenge);
         }
     }
 
-    fn check(&self) -> bool {
-        self.generate_solver()
+    // after `challenge` was loaded from the menu or a shortcut
+    fn challenge_loaded(&mut self, challenge: Challenges) {
+        self.dirty = true;
+        self.challenge_index = Challenges::iter().position(|c| *c == challenge);
+        self.challenge_timer = Some((challenge, Instant::now()));
+        self.message_text = format!("Loaded {}.", challenge);
+    }
+
+    // right clicking a token on the grid or in the bank opens a menu previewing each orientation
+    // the token may legally take in its cell
+    fn handle_orientation_context_menus(
+        &mut self,
+        grid_responses: &[eframe::egui::Response; 25],
+        bank_responses: &[eframe::egui::Response; 11],
+    ) {
+        // the solver knows which orientations point off the board or into the cell blocker
+        let node = self
+            .generate_solver()
             .stack
             .pop()
-            .expect("LaserMazeSolver initializes with a node")
-            .check()
-            .solved()
-    }
+            .expect("LaserMazeSolver initializes with a node");
 
-    fn run_solver(&self) -> Result<Option<[Option<Token>; 25]>, String> {
-        self.generate_solver().solve()
+        for (i, response) in grid_responses.iter().enumerate() {
+            if let Some(token) = self.tokens.grid[i].as_ref() {
+                let orientation_indices =
+                    node.orientation_iter(token.type_(), GuiIndex::new(i).to_model().get());
+                if let Some(orientation) =
+                    self.orientation_context_menu(response, token, &orientation_indices)
+                {
+                    self.tokens.grid[i]
+                        .as_mut()
+                        .expect("We just validated there is a token in this cell")
+                        .orientation = orientation.clone();
+                    self.dirty = true;
+                    self.record_move(MoveKind::Orient {
+                        cell: i,
+                        orientation,
+                    });
+                }
+            }
+        }
+
+        for (i, response) in bank_responses.iter().enumerate() {
+            if let Some(token) = self.tokens.bank[i].as_ref() {
+                // tokens in the bank aren't on the grid, so only the token's symmetry matters
+                let orientation_indices = token.type_().orientation_range();
+                if let Some(orientation) =
+                    self.orientation_context_menu(response, token, &orientation_indices)
+                {
+                    self.tokens.bank[i]
+                        .as_mut()
+                        .expect("We just validated there is a token in this cell")
+                        .orientation = orientation.clone();
+                    self.dirty = true;
+                    self.record_move(MoveKind::Orient {
+                        cell: 25 + i,
+                        orientation,
+                    });
+                }
+            }
+        }
     }
 
-    #[allow(clippy::needless_range_loop)]
-    fn solve(&mut self) -> Result<bool, String> {
-        if let Some(solved_grid) = self.run_solver()? {
-            self.tokens.to_be_added = Default::default();
-            for i in 0..25 {
-                // Allow clippy lint `needless_range_loop` because of different index systems
-                let transformed_index = Self::translate_model_index(i);
-                self.tokens.grid[transformed_index].clone_from(&solved_grid[i])
+    // returns Some(new_orientation) if the user picked an entry from the menu; picking
+    // "Unknown" returns Some(None)
+    fn orientation_context_menu(
+        &self,
+        response: &eframe::egui::Response,
+        token: &Token,
+        orientation_indices: &[usize],
+    ) -> Option<Option<Orientation>> {
+        let mut selection = None;
+        response.clone().context_menu(|ui| {
+            ui.label("Orientation:");
+            ui.horizontal(|ui| {
+                for orientation_index in orientation_indices {
+                    let orientation = Orientation::from_index(*orientation_index);
+                    let mut preview = token.clone();
+                    preview.orientation = Some(orientation.clone());
+                    if Cell::new(self.cell_size * 0.5, self.tint_tokens)
+                        .show(ui, &self.images, &Some(preview))
+                        .on_hover_text(format!("{:?}", orientation))
+                        .clicked()
+                    {
+                        selection = Some(Some(orientation));
+                        ui.close_menu();
+                    }
+                }
+            });
+            if ui.button("Unknown").clicked() {
+                selection = Some(None);
+                ui.close_menu();
             }
+        });
+        selection
+    }
+
+    // the maze isn't solved while there are still tokens to be added, the same as for the solver
+    fn check(&self) -> Result<bool, CheckError> {
+        check_board_with_unplaced(
+            self.tokens.model_grid(),
+            &self.tokens.to_be_added_tokens(),
+            self.tokens.targets,
+            &self.tokens.model_waypoints(),
+            &self.tokens.model_forbidden(),
+            &self.mirror_rules(),
+        )
+    }
+
+    // whether the laser lights the targets, ignoring any tokens still to be added
+    fn lit_ignoring_unplaced(&self) -> bool {
+        matches!(
+            check_board_with_unplaced(
+                self.tokens.model_grid(),
+                &[],
+                self.tokens.targets,
+                &self.tokens.model_waypoints(),
+                &self.tokens.model_forbidden(),
+                &self.mirror_rules(),
+            ),
             Ok(true)
-        } else {
-            Ok(false)
+        )
+    }
+
+    // a hint for new players whose board is missing pieces: how many target mirrors and beam splitters
+    // are still in the bank, and how many tokens are still to be added
+    fn remaining_tokens_hint(&self) -> Option<String> {
+        let count_in_bank = |type_: TokenType| {
+            self.tokens
+                .bank
+                .iter()
+                .flatten()
+                .filter(|token| token.type_() == &type_)
+                .count()
+        };
+        let targets_in_bank = count_in_bank(TokenType::TargetMirror);
+        let splitters_in_bank = count_in_bank(TokenType::BeamSplitter);
+        let to_be_added = self.tokens.to_be_added.iter().flatten().count();
+        if targets_in_bank + splitters_in_bank + to_be_added == 0 {
+            return None;
         }
+        Some(format!(
+            "Hint: {} target mirror(s) and {} beam splitter(s) are still in the bank, and {} token(s) are still to be added.",
+            targets_in_bank, splitters_in_bank, to_be_added
+        ))
     }
 
-    fn generate_solver(&self) -> LaserMazeSolver {
-        let mut grid: [Option<Token>; 25] = Default::default();
-        for i in 0..25 {
-            let transformed_index = Self::translate_model_index(i);
-            grid[transformed_index].clone_from(&self.tokens.grid[i]);
+    // a warning for players who forgot the targets slider: the number of targets can't be below the
+    // number of target mirrors which must be lit, or above the number of target mirrors (though the
+    // slider doesn't go that high)
+    fn targets_hint(&self) -> Option<String> {
+        let grid = self.tokens.model_grid();
+        let to_be_added = self.tokens.to_be_added_tokens();
+        let (fewest, most) = targets_bounds(&grid, &to_be_added);
+        // nothing to go on until a target mirror is on the board
+        if most == 0 || (fewest..=most).contains(&self.tokens.targets) {
+            return None;
         }
+        Some(format!(
+            "Hint: this board seems to need {} target(s).",
+            suggested_targets(&grid, &to_be_added)
+        ))
+    }
+
+    // trace the beam across the board, and keep the lit tokens in gui coordinates for the tooltips
+    fn record_checked_cells(&mut self) {
+        self.checked_cells = traced_cells(&self.tokens.model_grid(), &self.mirror_rules())
+            .ok()
+            .map(|cells| {
+                let mut gui_cells: [Option<Token>; 25] = Default::default();
+                for (i, token) in cells.into_iter().enumerate() {
+                    gui_cells[ModelIndex::new(i).to_gui().get()] = token;
+                }
+                (self.tokens.clone(), gui_cells)
+            });
+    }
+
+    // every problem with the board, so the user can fix them all at once
+    fn validate(&self) -> Vec<String> {
+        self.generate_solver().validate_all()
+    }
 
-        let mut to_be_added = vec![];
-        for token in self.tokens.to_be_added.iter().flatten() {
-            to_be_added.push(token.clone());
+    // if the board of the timed challenge is solved, under the official rules, record the time
+    fn stop_challenge_timer_if_solved(&mut self) {
+        if self.challenge_timer.is_none()
+            || self.transparent_double_mirror
+            || !matches!(self.check(), Ok(true))
+        {
+            return;
+        }
+        if let Some((challenge, started)) = self.challenge_timer.take() {
+            let time = started.elapsed();
+            let best = if self.best_times.record(challenge, time) {
+                " That's your best time!".to_string()
+            } else {
+                let best = self
+                    .best_times
+                    .get(challenge)
+                    .expect("We just recorded a time");
+                format!(" Your best time is {}.", format_time(best))
+            };
+            self.message_text = format!("Solved {} in {}.{}", challenge, format_time(time), best);
         }
+    }
 
-        LaserMazeSolver::new(grid, to_be_added, self.tokens.targets)
+    // run the solver on the tokio runtime, so solving hard puzzles doesn't freeze the gui
+    fn start_solving(&mut self, ctx: &eframe::egui::Context) {
+        // a challenge solved by the solver doesn't count for the best times
+        self.challenge_timer = None;
+        // an invalid board and a board with no solution need different fixes, so tell them apart
+        let problems = self.validate();
+        if !problems.is_empty() {
+            self.message_text = invalid_message(&problems);
+            return;
+        }
+        if let Some((_, _, solved_grid)) =
+            self.solution_cache
+                .as_ref()
+                .filter(|(cached_tokens, mirror_rules, _)| {
+                    *cached_tokens == self.tokens && *mirror_rules == self.mirror_rules()
+                })
+        {
+            let solved_grid = solved_grid.clone();
+            self.show_solution(ctx, &solved_grid, self.tokens.clone(), "solved before");
+            return;
+        }
+        let events = self.generate_solver().solve_channel();
+        self.solving = Some((events, self.tokens.clone(), Instant::now()));
+        self.message_text = "Solving...".into();
     }
 
-    // because of how egui adds items, the gui has cell 0 at top left, while the model
-    // was built with cell 0 as bottom left.
-    // luckily this operation is symmetric so we don't need a similar match statement
-    pub fn translate_model_index(index: usize) -> usize {
-        match index {
-            0..=4 => index + 20,
-            5..=9 => index + 10,
-            10..=14 => index,
-            15..=19 => index - 10,
-            20..=24 => index - 20,
-            _ => {
-                panic!("index out of grid range")
+    // handle the events the solver sent since the last frame
+    fn poll_solver(&mut self, ctx: &eframe::egui::Context) {
+        while let Some((events, _, started)) = self.solving.as_mut() {
+            let elapsed = started.elapsed();
+            match events.try_recv() {
+                Ok(SolveEvent::NodeExpanded(nodes_searched)) => {
+                    self.message_text = format!("Solving... ({} nodes searched)", nodes_searched);
+                }
+                Ok(SolveEvent::SolutionFound(solved_grid)) => {
+                    let (_, pre_solve_tokens, _) = self
+                        .solving
+                        .take()
+                        .expect("We just validated that the solver is running");
+                    self.solution_cache = Some((
+                        pre_solve_tokens.clone(),
+                        self.mirror_rules(),
+                        solved_grid.clone(),
+                    ));
+                    self.show_solution(
+                        ctx,
+                        &solved_grid,
+                        pre_solve_tokens,
+                        &format!("solved in {}ms", elapsed.as_millis()),
+                    );
+                }
+                Ok(SolveEvent::Exhausted) => {
+                    self.solving = None;
+                    self.message_text = format!(
+                        "No solution exists for this laser maze. (searched in {}ms)",
+                        elapsed.as_millis()
+                    );
+                }
+                Ok(SolveEvent::Error(s)) => {
+                    self.solving = None;
+                    self.message_text = format!("Error while running solver: {}", s);
+                }
+                Err(TryRecvError::Empty) => {
+                    // check again next frame
+                    ctx.request_repaint();
+                    return;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    self.solving = None;
+                    self.message_text = "The solver stopped unexpectedly!".into();
+                }
             }
         }
     }
 
+    // put the solution on the board; practice mode takes it away again after a moment, otherwise it
+    // can be reverted to `pre_solve_tokens`. `note` is shown in brackets after the message
+    fn show_solution(
+        &mut self,
+        ctx: &eframe::egui::Context,
+        solved_grid: &[Option<Token>; 25],
+        pre_solve_tokens: Tokens,
+        note: &str,
+    ) {
+        self.apply_solution(solved_grid);
+        self.solved_tray = self
+            .keep_tray
+            .then(|| (self.tokens.clone(), pre_solve_tokens.to_be_added.clone()));
+        if self.practice_mode {
+            self.practice_flash =
+                Some((pre_solve_tokens, Instant::now() + PRACTICE_FLASH_DURATION));
+            ctx.request_repaint_after(PRACTICE_FLASH_DURATION);
+            self.message_text = format!("Here's the solution, memorize it! ({})", note);
+        } else {
+            self.pre_solve_tokens = Some(pre_solve_tokens);
+            self.message_text = format!("Here's the solution! ({})", note);
+        }
+    }
+
+    fn apply_solution(&mut self, solved_grid: &[Option<Token>; 25]) {
+        self.tokens.to_be_added = Default::default();
+        for (i, token) in solved_grid.iter().enumerate() {
+            self.tokens.grid[ModelIndex::new(i).to_gui().get()].clone_from(token)
+        }
+        self.dirty = true;
+    }
+
+    // uses LaserMazeSolver::new rather than ::try_new; the board may be mid-edit, and the Validate
+    // and Solve buttons report invalid puzzles
+    fn generate_solver(&self) -> LaserMazeSolver {
+        self.tokens
+            .solver()
+            .with_mirror_rules(self.mirror_rules())
+            .with_placement_order(self.placement_order)
+    }
+
+    fn mirror_rules(&self) -> MirrorRules {
+        if self.transparent_double_mirror {
+            MirrorRules::transparent_double_mirror()
+        } else {
+            MirrorRules::official()
+        }
+    }
+
     #[allow(dead_code)]
     pub fn change_grid(&mut self, new_grid: [Option<Token>; 25]) {
         // accepts the coordinates used by the Solver, not visual coords
         for i in 0..25 {
-            self.tokens.grid[i].clone_from(&new_grid[Self::translate_model_index(i)]);
+            self.tokens.grid[i].clone_from(&new_grid[GuiIndex::new(i).to_model().get()]);
         }
+        self.dirty = true;
     }
 
     pub fn print_tokens_to_console(&self) {
-        let text = serde_json::to_string(&self.tokens).unwrap();
+        let text = saved_board::save_tokens(&self.tokens);
         println!("\n{text}\n");
     }
+
+    // an arrow from the cell out over the edge of the board
+    fn paint_beam_exit(ui: &egui::Ui, rect: egui::Rect, orientation: &Orientation) {
+        // north is up, like the model
+        let direction = match orientation {
+            Orientation::North => vec2(0., -1.),
+            Orientation::East => vec2(1., 0.),
+            Orientation::South => vec2(0., 1.),
+            Orientation::West => vec2(-1., 0.),
+        };
+        ui.painter().arrow(
+            rect.center(),
+            direction * rect.width() * 0.8,
+            egui::Stroke::new(rect.width() * 0.06, egui::Color32::RED),
+        );
+    }
+
+    // a line from each cell the beam crosses or leaves to the next cell (or the edge of the board),
+    // and a cross where beams intersect. `rects` are the grid's cells, by gui index
+    fn paint_beam_path(ui: &egui::Ui, rects: &[egui::Response], path: &BeamPath) {
+        let width = rects[0].rect.width();
+        let stroke = egui::Stroke::new(width * 0.04, egui::Color32::RED);
+        for model_index in 0..25 {
+            let model_index = ModelIndex::new(model_index);
+            let rect = rects[model_index.to_gui().get()].rect;
+            for orientation in path.directions_at(model_index.get()) {
+                let end = match model_index.neighbor(&orientation) {
+                    Some(neighbor) => rects[neighbor.to_gui().get()].rect.center(),
+                    // north is up, like the model
+                    None => match orientation {
+                        Orientation::North => rect.center_top(),
+                        Orientation::East => rect.right_center(),
+                        Orientation::South => rect.center_bottom(),
+                        Orientation::West => rect.left_center(),
+                    },
+                };
+                ui.painter().line_segment([rect.center(), end], stroke);
+            }
+            if path.beams_intersect_at(model_index.get()) {
+                let arm = width * 0.12;
+                let cross_stroke = egui::Stroke::new(width * 0.06, egui::Color32::YELLOW);
+                let center = rect.center();
+                ui.painter().line_segment(
+                    [center - vec2(arm, 0.), center + vec2(arm, 0.)],
+                    cross_stroke,
+                );
+                ui.painter().line_segment(
+                    [center - vec2(0., arm), center + vec2(0., arm)],
+                    cross_stroke,
+                );
+            }
+        }
+    }
+
+    // highlight the target mirror whose must light flag was just toggled, until the flash ends
+    fn paint_must_light_flash(
+        &mut self,
+        ctx: &eframe::egui::Context,
+        grid_responses: &[eframe::egui::Response; 25],
+        bank_responses: &[eframe::egui::Response; 11],
+        to_be_added_responses: &[eframe::egui::Response; 6],
+    ) {
+        let (index, flash_end) = match self.must_light_flash {
+            Some(flash) => flash,
+            None => return,
+        };
+        let now = Instant::now();
+        if now >= flash_end {
+            self.must_light_flash = None;
+            return;
+        }
+        if let Some(response) = grid_responses
+            .iter()
+            .chain(bank_responses.iter())
+            .chain(to_be_added_responses.iter())
+            .nth(index)
+        {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("must_light_flash"),
+            ));
+            painter.rect_filled(
+                response.rect,
+                0.,
+                egui::Color32::from_rgba_unmultiplied(255, 255, 0, 80),
+            );
+        }
+        ctx.request_repaint_after(flash_end - now);
+    }
+
+    // the gui index in the top left corner, and the model index in the bottom right
+    fn paint_cell_indices(ui: &egui::Ui, rect: egui::Rect, gui_index: usize) {
+        let font_id = egui::FontId::monospace(rect.width() * 0.15);
+        let inset = vec2(rect.width() * 0.05, rect.width() * 0.05);
+        ui.painter().text(
+            rect.left_top() + inset,
+            egui::Align2::LEFT_TOP,
+            gui_index.to_string(),
+            font_id.clone(),
+            egui::Color32::YELLOW,
+        );
+        ui.painter().text(
+            rect.right_bottom() - inset,
+            egui::Align2::RIGHT_BOTTOM,
+            GuiIndex::new(gui_index).to_model().get().to_string(),
+            font_id,
+            egui::Color32::LIGHT_BLUE,
+        );
+    }
+
+    fn import_board_image(&mut self, file: egui::DroppedFile) {
+        let bytes = match (&file.bytes, &file.path) {
+            (Some(bytes), _) => Ok(bytes.to_vec()),
+            (None, Some(path)) => std::fs::read(path).map_err(|e| e.to_string()),
+            (None, None) => Err(String::from("The dropped file is empty!")),
+        };
+        match bytes.and_then(|bytes| board_import::tokens_from_image(&bytes)) {
+            Ok(tokens) => {
+                self.tokens = tokens;
+                self.dirty = true;
+                self.challenge_timer = None;
+                self.message_text = format!(
+                    "Imported the board from {}; check it, then set the tokens to be added and the number of targets.",
+                    file.name
+                );
+            }
+            Err(s) => self.message_text = format!("Couldn't import the board: {}", s),
+        }
+    }
+
+    pub fn print_moves_to_console(&self) {
+        if let Some(move_recorder) = &self.move_recorder {
+            println!("\n{}\n", move_recorder.to_json());
+        }
+    }
 }
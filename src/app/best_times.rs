@@ -0,0 +1,62 @@
+use crate::app::challenges::Challenges;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+// where the best times are kept in eframe's storage
+pub const STORAGE_KEY: &str = "best_times";
+
+/// BestTimes: the fastest the user has solved each included challenge by hand, kept between runs.
+/// Keyed by the challenge's name, so adding challenges doesn't mix up the times
+#[derive(Default, Serialize, Deserialize)]
+pub struct BestTimes {
+    times: HashMap<String, Duration>,
+}
+
+impl BestTimes {
+    pub fn get(&self, challenge: Challenges) -> Option<Duration> {
+        self.times.get(&challenge.to_string()).copied()
+    }
+
+    // returns true if `time` is the best time for `challenge` so far
+    pub fn record(&mut self, challenge: Challenges, time: Duration) -> bool {
+        let best = self.times.entry(challenge.to_string()).or_insert(time);
+        if time <= *best {
+            *best = time;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A time in minutes and seconds, e.g. "1:05"
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut best_times = BestTimes::default();
+        assert_eq!(best_times.get(Challenges::BonusChallenge1), None);
+        assert!(best_times.record(Challenges::BonusChallenge1, Duration::from_secs(90)));
+        assert!(!best_times.record(Challenges::BonusChallenge1, Duration::from_secs(100)));
+        assert!(best_times.record(Challenges::BonusChallenge1, Duration::from_secs(65)));
+        assert_eq!(
+            best_times.get(Challenges::BonusChallenge1),
+            Some(Duration::from_secs(65))
+        );
+        assert_eq!(best_times.get(Challenges::BonusChallenge2), None);
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(Duration::from_millis(65_900)), "1:05");
+        assert_eq!(format_time(Duration::from_secs(3600)), "60:00");
+    }
+}
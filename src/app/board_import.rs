@@ -0,0 +1,191 @@
+use crate::app::Tokens;
+use crate::solver::orientation::Orientation;
+use crate::solver::token::{Token, TokenType};
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+
+// cells are compared at this size, in pixels
+const SAMPLE_SIZE: u32 = 32;
+
+// what a cell looks like with this token (or no token) in it
+struct Template {
+    token: Option<Token>,
+    image: RgbaImage,
+}
+
+/// Build a board from a picture of the 5x5 grid, cropped to the edges of the grid, by comparing
+/// each cell to the token images in assets/. Only PNG is supported. The tokens to be added and
+/// the number of targets are left for the user to fill in
+pub fn tokens_from_image(bytes: &[u8]) -> Result<Tokens, String> {
+    let board = image::load_from_memory(bytes)
+        .map_err(|e| format!("Couldn't read the image: {}", e))?
+        .to_rgba8();
+    tokens_from_rgba(&board)
+}
+
+fn tokens_from_rgba(board: &RgbaImage) -> Result<Tokens, String> {
+    let cell_width = board.width() / 5;
+    let cell_height = board.height() / 5;
+    if cell_width == 0 || cell_height == 0 {
+        return Err(String::from("The image is too small!"));
+    }
+
+    let templates = templates();
+    let mut grid: [Option<Token>; 25] = Default::default();
+    for (i, cell) in grid.iter_mut().enumerate() {
+        // like the gui, cell 0 is top left
+        let (row, column) = ((i / 5) as u32, (i % 5) as u32);
+        let sample = imageops::resize(
+            &imageops::crop_imm(
+                board,
+                column * cell_width,
+                row * cell_height,
+                cell_width,
+                cell_height,
+            )
+            .to_image(),
+            SAMPLE_SIZE,
+            SAMPLE_SIZE,
+            FilterType::Triangle,
+        );
+        *cell = templates
+            .iter()
+            .min_by_key(|template| distance(&sample, &template.image))
+            .expect("There is always a template for an empty cell")
+            .token
+            .clone();
+    }
+
+    Ok(Tokens {
+        grid,
+        to_be_added: Default::default(),
+        bank: Default::default(),
+        targets: 1,
+        waypoints: Default::default(),
+        forbidden: Default::default(),
+    })
+}
+
+fn load_asset(bytes: &[u8]) -> RgbaImage {
+    image::load_from_memory(bytes)
+        .expect("failed to load asset")
+        .to_rgba8()
+}
+
+fn templates() -> Vec<Template> {
+    let cell_empty = load_asset(include_bytes!(r#"../../assets/cell_empty.png"#));
+    let mut result = vec![Template {
+        token: None,
+        image: imageops::resize(&cell_empty, SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Triangle),
+    }];
+
+    let token_images: [(TokenType, bool, &[u8]); 7] = [
+        (
+            TokenType::Laser,
+            false,
+            include_bytes!(r#"../../assets/token_laser.png"#),
+        ),
+        (
+            TokenType::TargetMirror,
+            false,
+            include_bytes!(r#"../../assets/token_target_mirror.png"#),
+        ),
+        (
+            TokenType::TargetMirror,
+            true,
+            include_bytes!(r#"../../assets/token_target_mirror_must_light.png"#),
+        ),
+        (
+            TokenType::BeamSplitter,
+            false,
+            include_bytes!(r#"../../assets/token_beam_splitter.png"#),
+        ),
+        (
+            TokenType::DoubleMirror,
+            false,
+            include_bytes!(r#"../../assets/token_double_mirror.png"#),
+        ),
+        (
+            TokenType::Checkpoint,
+            false,
+            include_bytes!(r#"../../assets/token_checkpoint.png"#),
+        ),
+        (
+            TokenType::CellBlocker,
+            false,
+            include_bytes!(r#"../../assets/token_cell_blocker.png"#),
+        ),
+    ];
+    for (type_, must_light, bytes) in token_images {
+        // the token images are drawn over the empty cell, like in Cell::show
+        let mut image = cell_empty.clone();
+        imageops::overlay(&mut image, &load_asset(bytes), 0, 0);
+        let mut image = imageops::resize(&image, SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Triangle);
+        let orientation_range = type_.orientation_range();
+        // the images point north; each orientation is another quarter turn clockwise
+        for orientation_index in 0..4 {
+            if orientation_range.contains(&orientation_index) {
+                result.push(Template {
+                    token: Some(Token::new(
+                        type_,
+                        Some(Orientation::from_index(orientation_index)),
+                        must_light,
+                    )),
+                    image: image.clone(),
+                });
+            }
+            image = imageops::rotate90(&image);
+        }
+    }
+    result
+}
+
+// sum of squared differences of the color channels; alpha is ignored
+fn distance(a: &RgbaImage, b: &RgbaImage) -> u64 {
+    a.pixels()
+        .zip(b.pixels())
+        .map(|(p, q)| {
+            p.0.iter()
+                .zip(q.0.iter())
+                .take(3)
+                .map(|(x, y)| (*x as i64 - *y as i64).pow(2) as u64)
+                .sum::<u64>()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tokens_from_rgba_round_trip() {
+        // lay the templates out as a board, and read it back
+        let templates = templates();
+        let mut board = RgbaImage::new(5 * SAMPLE_SIZE, 5 * SAMPLE_SIZE);
+        let mut expected: [Option<Token>; 25] = Default::default();
+        for i in 0..25 {
+            let template = &templates[i % templates.len()];
+            let (row, column) = ((i / 5) as u32, (i % 5) as u32);
+            imageops::replace(
+                &mut board,
+                &template.image,
+                (column * SAMPLE_SIZE) as i64,
+                (row * SAMPLE_SIZE) as i64,
+            );
+            expected[i] = template.token.clone();
+        }
+
+        let tokens = tokens_from_rgba(&board).unwrap();
+        assert_eq!(tokens.grid, expected);
+    }
+
+    #[test]
+    fn test_tokens_from_image_errors() {
+        assert!(tokens_from_image(b"not a png").is_err());
+        assert_eq!(
+            tokens_from_rgba(&RgbaImage::new(4, 4)).err(),
+            Some(String::from("The image is too small!"))
+        );
+    }
+}
@@ -1,9 +1,53 @@
-use crate::app::Tokens;
+use crate::app::{saved_board::load_tokens, Tokens};
 use lazy_static::lazy_static;
 use std::fmt;
 
-#[derive(Clone, Copy, PartialEq)]
+/// Challenge: a puzzle with its details for the catalog
+#[derive(Clone)]
+pub struct Challenge {
+    pub name: String,
+    // 1 (beginner) to 4 (expert), like the levels on the cards
+    pub difficulty: u8,
+    pub tokens: Tokens,
+}
+
+impl Challenge {
+    pub fn new(name: String, difficulty: u8, tokens: Tokens) -> Self {
+        Self {
+            name,
+            difficulty,
+            tokens,
+        }
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (difficulty {}/4)", self.name, self.difficulty)
+    }
+}
+
+/// A difficulty for a puzzle without one, on the cards' 1 to 4 scale: the more tokens left to place
+/// or orient, the harder
+pub fn estimated_difficulty(tokens: &Tokens) -> u8 {
+    let unoriented_on_grid = tokens
+        .grid
+        .iter()
+        .flatten()
+        .filter(|token| token.orientation().is_none())
+        .count();
+    let to_be_added = tokens.to_be_added.iter().flatten().count();
+    match unoriented_on_grid + to_be_added {
+        0..=3 => 1,
+        4..=5 => 2,
+        6..=7 => 3,
+        _ => 4,
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
 pub enum Challenges {
+    #[default]
     BonusChallenge1,
     BonusChallenge2,
     BonusChallenge3,
@@ -19,16 +63,36 @@ lazy_static! {
         Challenges::BonusChallenge3,
         Challenges::BonusChallenge26,
     ];
+    // the menu's label for each challenge in CHALLENGE_ORDER, made once: estimating a challenge's
+    // difficulty means parsing its saved board
+    static ref CHALLENGE_LABELS: Vec<String> = CHALLENGE_ORDER
+        .iter()
+        .map(|challenge| challenge.challenge().to_string())
+        .collect();
 }
 
-impl Default for Challenges {
-    fn default() -> Self {
-        Self::BonusChallenge1
+impl Challenges {
+    // the bonus challenges aren't rated, so their difficulty is estimated
+    pub fn challenge(&self) -> Challenge {
+        let tokens = self.saved_tokens();
+        Challenge::new(self.to_string(), estimated_difficulty(&tokens), tokens)
     }
-}
 
-impl Challenges {
     pub fn tokens(&self) -> Tokens {
+        self.challenge().tokens
+    }
+
+    /// The challenge's name and difficulty, as listed in the menu
+    pub fn label(&self) -> &'static str {
+        let index = CHALLENGE_ORDER
+            .iter()
+            .position(|challenge| challenge == self)
+            .expect("Every challenge is in CHALLENGE_ORDER");
+        &CHALLENGE_LABELS[index]
+    }
+
+    // the board as saved with the app
+    fn saved_tokens(&self) -> Tokens {
         let text = match self {
             Challenges::BonusChallenge1 => {
                 r#"{"targets":3,"grid":[null,{"type_":"TargetMirror","orientation":null,"lit":false,"target_lit":false,"must_light":false},null,null,{"type_":"TargetMirror","orientation":null,"lit":false,"target_lit":false,"must_light":false},null,null,null,null,null,null,null,null,null,null,{"type_":"Laser","orientation":null,"lit":true,"target_lit":null,"must_light":false},{"type_":"CellBlocker","orientation":"North","lit":true,"target_lit":null,"must_light":false},{"type_":"BeamSplitter","orientation":"North","lit":false,"target_lit":null,"must_light":false},{"type_":"Checkpoint","orientation":null,"lit":false,"target_lit":null,"must_light":false},null,null,null,null,null,{"type_":"TargetMirror","orientation":"East","lit":false,"target_lit":false,"must_light":false}],"to_be_added":[{"type_":"TargetMirror","orientation":null,"lit":false,"target_lit":false,"must_light":false},{"type_":"TargetMirror","orientation":null,"lit":false,"target_lit":false,"must_light":false},{"type_":"DoubleMirror","orientation":null,"lit":false,"target_lit":null,"must_light":false},{"type_":"BeamSplitter","orientation":null,"lit":false,"target_lit":null,"must_light":false},null,null],"bank":[null,null,null,null,null,null,null,null,null,null,null]}"#
@@ -43,7 +107,7 @@ impl Challenges {
                 r#"{"grid":[null,null,null,null,null,null,null,null,null,{"type_":"TargetMirror","orientation":null,"lit":false,"target_lit":false,"must_light":false},null,{"type_":"Checkpoint","orientation":null,"lit":false,"target_lit":null,"must_light":false},null,{"type_":"DoubleMirror","orientation":null,"lit":false,"target_lit":null,"must_light":false},null,null,null,null,null,null,null,{"type_":"CellBlocker","orientation":"North","lit":true,"target_lit":null,"must_light":false},{"type_":"BeamSplitter","orientation":"East","lit":false,"target_lit":null,"must_light":false},null,null],"to_be_added":[{"type_":"Laser","orientation":null,"lit":true,"target_lit":null,"must_light":false},{"type_":"TargetMirror","orientation":null,"lit":false,"target_lit":false,"must_light":false},{"type_":"TargetMirror","orientation":null,"lit":false,"target_lit":false,"must_light":false},{"type_":"TargetMirror","orientation":null,"lit":false,"target_lit":false,"must_light":false},{"type_":"BeamSplitter","orientation":null,"lit":false,"target_lit":null,"must_light":false},null],"bank":[{"type_":"TargetMirror","orientation":"South","lit":false,"target_lit":false,"must_light":false},null,null,null,null,null,null,null,null,null,null],"targets":2}"#
             }
         };
-        serde_json::from_str(text).unwrap()
+        load_tokens(text).expect("The included challenges are valid boards")
     }
 
     pub fn iter() -> std::slice::Iter<'static, Challenges> {
@@ -94,3 +158,34 @@ impl_display_for_challenges!(
     BonusChallenge3,
     BonusChallenge26
 );
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_challenge() {
+        let challenge = Challenges::BonusChallenge1.challenge();
+        assert_eq!(challenge.name, "Bonus Challenge 1");
+        // 4 tokens to be added and 4 unoriented on the grid
+        assert_eq!(challenge.difficulty, 4);
+        assert_eq!(challenge.to_string(), "Bonus Challenge 1 (difficulty 4/4)");
+        assert_eq!(
+            Challenges::BonusChallenge1.label(),
+            "Bonus Challenge 1 (difficulty 4/4)"
+        );
+        assert_eq!(estimated_difficulty(&Tokens::default()), 1);
+    }
+
+    #[test]
+    fn test_challenges_are_solvable() {
+        for challenge in Challenges::iter() {
+            let mut solver = challenge.tokens().solver();
+            assert!(
+                matches!(solver.solve(), Ok(Some(_))),
+                "{} should be solvable",
+                challenge
+            );
+        }
+    }
+}
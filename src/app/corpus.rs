@@ -0,0 +1,86 @@
+// the regression corpus: every board saved in tests/fixtures must be solvable, and the solver's
+// solution must keep the board's fixed tokens and check as solved. a solution doesn't have to match
+// any particular layout, so changes to the search order don't break the corpus. where a
+// <name>.solution.json sits next to <name>.json, that known solution must check as solved too. to add
+// a puzzle, save it with "Print to console" and drop the JSON in the directory
+use crate::app::saved_board::load_tokens;
+use crate::app::Tokens;
+use crate::solver::check_board_with_unplaced;
+use crate::solver::puzzle_def::{solution_matches_puzzle, PuzzleDef};
+use crate::solver::token::{MirrorRules, Token};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the boards in tests/fixtures, without their known solutions, in name order
+fn fixtures() -> Vec<PathBuf> {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut boards: Vec<PathBuf> = fs::read_dir(&fixtures)
+        .expect("tests/fixtures should exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.to_string_lossy();
+            name.ends_with(".json") && !name.ends_with(".solution.json")
+        })
+        .collect();
+    boards.sort();
+    boards
+}
+
+fn read(path: &Path) -> Tokens {
+    let json = fs::read_to_string(path).unwrap();
+    load_tokens(&json).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+// whether `solution` keeps the fixed tokens of `board`, places its tokens to be added, and lights
+// its targets under the official rules
+fn solves(board: &Tokens, solution: &[Option<Token>; 25]) -> bool {
+    let puzzle = PuzzleDef {
+        id: 0,
+        grid: board.model_grid(),
+        to_be_added: board.to_be_added_tokens(),
+        targets: board.targets,
+    };
+    solution_matches_puzzle(&puzzle, solution)
+        && check_board_with_unplaced(
+            solution.clone(),
+            &[],
+            board.targets,
+            &board.model_waypoints(),
+            &board.model_forbidden(),
+            &MirrorRules::default(),
+        ) == Ok(true)
+}
+
+#[test]
+fn test_fixtures_are_solvable() {
+    let boards = fixtures();
+    assert!(!boards.is_empty());
+    for path in boards {
+        let board = read(&path);
+        let solution = board
+            .solver()
+            .solve()
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+            .unwrap_or_else(|| panic!("{} should be solvable", path.display()));
+        assert!(
+            solves(&board, &solution),
+            "{} was solved with a board which doesn't solve it",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn test_fixture_solutions_check() {
+    for path in fixtures() {
+        let known = path.with_extension("solution.json");
+        if known.exists() {
+            assert!(
+                solves(&read(&path), &read(&known).model_grid()),
+                "{} doesn't solve {}",
+                known.display(),
+                path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use crate::solver::orientation::Orientation;
+
+// because of how egui adds items, the gui has cell 0 at top left, while the model
+// was built with cell 0 as bottom left. these types keep the two apart, so passing one
+// where the other is expected doesn't compile
+
+/// ModelIndex: a grid cell index in the solver's coordinates (cell 0 is bottom left)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModelIndex(u8);
+
+/// GuiIndex: a grid cell index as laid out on screen (cell 0 is top left)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GuiIndex(u8);
+
+// the conversion is the same both ways: the rows are flipped, the columns stay put
+fn flip_rows(index: u8) -> u8 {
+    (4 - index / 5) * 5 + index % 5
+}
+
+impl ModelIndex {
+    /// Panics if `index` is off the grid
+    pub fn new(index: usize) -> Self {
+        assert!(index < 25, "index out of grid range");
+        Self(index as u8)
+    }
+
+    pub fn get(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn to_gui(self) -> GuiIndex {
+        GuiIndex(flip_rows(self.0))
+    }
+
+    /// The next cell in the direction `orientation`, or None off the edge of the grid
+    pub fn neighbor(self, orientation: &Orientation) -> Option<ModelIndex> {
+        let (row, column) = (self.0 / 5, self.0 % 5);
+        match orientation {
+            Orientation::North if row < 4 => Some(ModelIndex(self.0 + 5)),
+            Orientation::East if column < 4 => Some(ModelIndex(self.0 + 1)),
+            Orientation::South if row > 0 => Some(ModelIndex(self.0 - 5)),
+            Orientation::West if column > 0 => Some(ModelIndex(self.0 - 1)),
+            _ => None,
+        }
+    }
+}
+
+impl GuiIndex {
+    /// Panics if `index` is off the grid
+    pub fn new(index: usize) -> Self {
+        assert!(index < 25, "index out of grid range");
+        Self(index as u8)
+    }
+
+    pub fn get(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn to_model(self) -> ModelIndex {
+        ModelIndex(flip_rows(self.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_gui() {
+        // bottom left, top right, and the center
+        assert_eq!(ModelIndex::new(0).to_gui(), GuiIndex::new(20));
+        assert_eq!(ModelIndex::new(24).to_gui(), GuiIndex::new(4));
+        assert_eq!(ModelIndex::new(12).to_gui(), GuiIndex::new(12));
+        assert_eq!(ModelIndex::new(7).to_gui(), GuiIndex::new(17));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for i in 0..25 {
+            assert_eq!(ModelIndex::new(i).to_gui().to_model(), ModelIndex::new(i));
+            assert_eq!(GuiIndex::new(i).to_model().to_gui(), GuiIndex::new(i));
+        }
+    }
+
+    #[test]
+    fn test_neighbor() {
+        let center = ModelIndex::new(12);
+        assert_eq!(
+            center.neighbor(&Orientation::North),
+            Some(ModelIndex::new(17))
+        );
+        assert_eq!(
+            center.neighbor(&Orientation::East),
+            Some(ModelIndex::new(13))
+        );
+        assert_eq!(
+            center.neighbor(&Orientation::South),
+            Some(ModelIndex::new(7))
+        );
+        assert_eq!(
+            center.neighbor(&Orientation::West),
+            Some(ModelIndex::new(11))
+        );
+        // the bottom right corner
+        let corner = ModelIndex::new(4);
+        assert_eq!(corner.neighbor(&Orientation::East), None);
+        assert_eq!(corner.neighbor(&Orientation::South), None);
+        assert_eq!(
+            corner.neighbor(&Orientation::West),
+            Some(ModelIndex::new(3))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_range() {
+        GuiIndex::new(25);
+    }
+}
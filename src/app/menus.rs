@@ -1,3 +1,4 @@
+use crate::app::best_times::{format_time, BestTimes};
 use crate::app::{challenges::Challenges, Tokens};
 use eframe::egui::{ComboBox, Context, Window};
 
@@ -5,31 +6,89 @@ use eframe::egui::{ComboBox, Context, Window};
 pub struct LoadIncludedChallengesMenu {
     pub open: bool,
     selected_challenge: Challenges,
+    // the board as of the last load (or the default board), so we can tell if the user edited it
+    last_loaded_tokens: Tokens,
+    confirm_load_open: bool,
 }
 
 impl LoadIncludedChallengesMenu {
-    pub fn show(&mut self, ctx: &Context, app_tokens: &mut Tokens) {
+    pub fn selected_challenge(&self) -> Challenges {
+        self.selected_challenge
+    }
+
+    // returns true if a challenge was loaded into `app_tokens`. each challenge is listed with the
+    // user's best time for it
+    pub fn show(&mut self, ctx: &Context, app_tokens: &mut Tokens, best_times: &BestTimes) -> bool {
+        let label = |challenge: Challenges| match best_times.get(challenge) {
+            Some(best) => format!("{}, best {}", challenge.label(), format_time(best)),
+            None => challenge.label().to_string(),
+        };
+        let mut loaded = false;
+        let mut load_clicked = false;
         Window::new("Load Included Challenges")
             .collapsible(true)
             .open(&mut self.open)
             .show(ctx, |ui| {
                 ComboBox::from_id_source("challenge_selector")
-                    .selected_text(format!("{}", &self.selected_challenge))
+                    .selected_text(label(self.selected_challenge))
                     .show_ui(ui, |ui| {
                         for challenge in Challenges::iter() {
                             let value = ui.selectable_value(
                                 &mut self.selected_challenge,
                                 *challenge,
-                                format!("{}", challenge),
+                                label(*challenge),
                             );
                             if value.clicked() {
                                 self.selected_challenge = *challenge;
                             }
                         }
                     });
-                if ui.button("Load").clicked() {
-                    *app_tokens = self.selected_challenge.tokens()
-                }
+                load_clicked = ui.button("Load").clicked();
             });
+        // the window borrows self.open, so the load waits until it's shown
+        if load_clicked {
+            loaded = self.load(self.selected_challenge, app_tokens);
+        }
+
+        if self.confirm_load_open {
+            Window::new("Discard Changes?")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "The board has been edited. Loading {} will discard your changes.",
+                        &self.selected_challenge
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("Load").clicked() {
+                            self.last_loaded_tokens = self.selected_challenge.tokens();
+                            *app_tokens = self.last_loaded_tokens.clone();
+                            self.confirm_load_open = false;
+                            loaded = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_load_open = false;
+                        }
+                    });
+                });
+        }
+
+        loaded
+    }
+
+    // loads `challenge` into `app_tokens` and returns true, unless the user has edited the board
+    // since the last load; then .show() asks before discarding the changes, and returns true if the
+    // user goes ahead
+    pub fn load(&mut self, challenge: Challenges, app_tokens: &mut Tokens) -> bool {
+        self.selected_challenge = challenge;
+        if *app_tokens == self.last_loaded_tokens {
+            self.last_loaded_tokens = challenge.tokens();
+            *app_tokens = self.last_loaded_tokens.clone();
+            true
+        } else {
+            // don't silently throw away the user's edits
+            self.confirm_load_open = true;
+            false
+        }
     }
 }
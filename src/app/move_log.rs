@@ -0,0 +1,55 @@
+use crate::solver::orientation::Orientation;
+use serde::Serialize;
+use std::time::Instant;
+
+/// MoveKind: a change the user made to the board. Cell indices are those of the grid, bank and
+/// to be added cells chained together, as in MyApp's handle_* functions (grid cell 0 is top left)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum MoveKind {
+    Move {
+        from: usize,
+        to: usize,
+    },
+    Orient {
+        cell: usize,
+        orientation: Option<Orientation>,
+    },
+    ToggleMustLight {
+        cell: usize,
+    },
+}
+
+/// MoveEvent: a move, and how many milliseconds after recording started it was made
+#[derive(Clone, Debug, Serialize)]
+pub struct MoveEvent {
+    pub millis: u64,
+    pub kind: MoveKind,
+}
+
+/// MoveRecorder: an opt-in log of the user's moves, for studying how people solve puzzles
+pub struct MoveRecorder {
+    started: Instant,
+    events: Vec<MoveEvent>,
+}
+
+impl Default for MoveRecorder {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            events: vec![],
+        }
+    }
+}
+
+impl MoveRecorder {
+    pub fn record(&mut self, kind: MoveKind) {
+        self.events.push(MoveEvent {
+            millis: self.started.elapsed().as_millis() as u64,
+            kind,
+        });
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.events).expect("MoveEvents are always serializable")
+    }
+}
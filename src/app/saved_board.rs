@@ -0,0 +1,121 @@
+use crate::app::Tokens;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+// the version written by save_tokens. bump it when the shape of Tokens changes, and teach
+// migrate() to upgrade the older versions
+pub const CURRENT_VERSION: u32 = 3;
+
+/// Reasons a saved board can't be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    // the text isn't JSON, or isn't the shape of a board
+    Json(serde_json::Error),
+    // the board was saved by a newer version of the app
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Json(e) => write!(f, "The board isn't valid: {}", e),
+            LoadError::UnsupportedVersion(version) => write!(
+                f,
+                "The board was saved as version {}, but only up to version {} can be loaded!",
+                version, CURRENT_VERSION
+            ),
+        }
+    }
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(e: serde_json::Error) -> Self {
+        LoadError::Json(e)
+    }
+}
+
+#[derive(Serialize)]
+struct SavedTokens<'a> {
+    version: u32,
+    #[serde(flatten)]
+    tokens: &'a Tokens,
+}
+
+/// The board as JSON, tagged with the current version
+pub fn save_tokens(tokens: &Tokens) -> String {
+    serde_json::to_string(&SavedTokens {
+        version: CURRENT_VERSION,
+        tokens,
+    })
+    .expect("Tokens are always serializable")
+}
+
+/// Read a board saved by save_tokens, or an untagged board from before versioning (version 1),
+/// upgrading older versions to the current shape
+pub fn load_tokens(json: &str) -> Result<Tokens, LoadError> {
+    let mut value: Value = serde_json::from_str(json)?;
+    // untagged boards predate versioning
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(1, |version| version as u32);
+    if version > CURRENT_VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+    migrate(&mut value, version);
+    Ok(serde_json::from_value(value)?)
+}
+
+fn migrate(value: &mut Value, version: u32) {
+    if let Some(object) = value.as_object_mut() {
+        // version 1 boards have no waypoints
+        if version < 2 {
+            object
+                .entry("waypoints")
+                .or_insert_with(|| Value::from(vec![false; 25]));
+        }
+        // version 2 boards have no forbidden cells
+        if version < 3 {
+            object
+                .entry("forbidden")
+                .or_insert_with(|| Value::from(vec![false; 25]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::challenges::Challenges;
+
+    #[test]
+    fn test_round_trip() {
+        let tokens = Challenges::BonusChallenge2.tokens();
+        let json = save_tokens(&tokens);
+        assert!(json.contains("\"version\":3"));
+        assert!(load_tokens(&json).unwrap() == tokens);
+    }
+
+    #[test]
+    fn test_load_version_1() {
+        // no version and no waypoints, like the included challenges
+        let tokens = load_tokens(r#"{"targets":1,"grid":[null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null],"to_be_added":[null,null,null,null,null,null],"bank":[null,null,null,null,null,null,null,null,null,null,null]}"#).unwrap();
+        assert_eq!(tokens.targets, 1);
+        assert_eq!(tokens.waypoints, [false; 25]);
+        assert_eq!(tokens.forbidden, [false; 25]);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert!(matches!(
+            load_tokens(r#"{"version":4}"#),
+            Err(LoadError::UnsupportedVersion(4))
+        ));
+        assert!(matches!(load_tokens("not json"), Err(LoadError::Json(_))));
+        assert!(matches!(
+            load_tokens(r#"{"version":2}"#),
+            Err(LoadError::Json(_))
+        ));
+    }
+}
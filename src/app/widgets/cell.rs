@@ -2,7 +2,7 @@ pub mod collections;
 
 use eframe::{
     egui::{vec2, Context, Image, Sense},
-    epaint::{pos2, Color32, Rect, Vec2},
+    epaint::{pos2, Color32, Rect, Stroke, Vec2},
 };
 
 use crate::{
@@ -12,11 +12,56 @@ use crate::{
 
 pub struct Cell {
     size: f32,
+    // tint each token by its type, and outline targets which must be lit
+    tint_tokens: bool,
+    // keyboard navigation: the selected cell, and the cell whose token was picked up
+    selected: bool,
+    held: bool,
+    // the laser must pass through this cell
+    waypoint: bool,
+    // the laser must not enter this cell
+    forbidden: bool,
+    // after Check: whether the token was lit, and for a target mirror, whether its target was
+    checked: Option<(bool, Option<bool>)>,
 }
 
 impl Cell {
-    pub fn new(size: f32) -> Self {
-        Self { size }
+    pub fn new(size: f32, tint_tokens: bool) -> Self {
+        Self {
+            size,
+            tint_tokens,
+            selected: false,
+            held: false,
+            waypoint: false,
+            forbidden: false,
+            checked: None,
+        }
+    }
+
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn held(mut self, held: bool) -> Self {
+        self.held = held;
+        self
+    }
+
+    pub fn waypoint(mut self, waypoint: bool) -> Self {
+        self.waypoint = waypoint;
+        self
+    }
+
+    pub fn forbidden(mut self, forbidden: bool) -> Self {
+        self.forbidden = forbidden;
+        self
+    }
+
+    // `checked_token` is this cell's token as the checker left it
+    pub fn checked(mut self, checked_token: Option<&Token>) -> Self {
+        self.checked = checked_token.map(|token| (token.lit, token.target_lit()));
+        self
     }
 
     pub fn show(
@@ -47,11 +92,119 @@ impl Cell {
             );
 
             if let Some(token_image) = Self::get_token_image(ui.ctx(), token, images, rect.size()) {
-                token_image.paint_at(ui, rect)
+                match token {
+                    Some(token) if self.tint_tokens => token_image
+                        .tint(Self::token_tint(token.type_()))
+                        .paint_at(ui, rect),
+                    _ => token_image.paint_at(ui, rect),
+                }
+            }
+
+            if let Some((true, _)) = self.checked {
+                // after Check, the tokens the laser touched glow
+                ui.painter().rect_filled(
+                    rect.shrink(self.size * 0.04),
+                    0.0,
+                    Color32::from_rgba_unmultiplied(240, 228, 66, 60),
+                );
+            }
+
+            if let Some(token) = token {
+                if self.tint_tokens && token.must_light() {
+                    ui.painter().rect_stroke(
+                        rect.shrink(self.size * 0.04),
+                        0.0,
+                        Stroke::new(self.size * 0.04, Color32::from_rgb(230, 159, 0)),
+                    );
+                }
+            }
+
+            if self.waypoint {
+                // a ring in the top left corner, clear of the token art
+                ui.painter().circle_stroke(
+                    rect.min + vec2(self.size * 0.15, self.size * 0.15),
+                    self.size * 0.07,
+                    Stroke::new(self.size * 0.03, Color32::from_rgb(0, 158, 115)),
+                );
+            }
+            if self.forbidden {
+                // a cross in the top right corner, opposite the waypoint ring
+                let center = rect.right_top() + vec2(-self.size * 0.15, self.size * 0.15);
+                let arm = self.size * 0.06;
+                let stroke = Stroke::new(self.size * 0.03, Color32::from_rgb(213, 94, 0));
+                ui.painter()
+                    .line_segment([center + vec2(-arm, -arm), center + vec2(arm, arm)], stroke);
+                ui.painter()
+                    .line_segment([center + vec2(-arm, arm), center + vec2(arm, -arm)], stroke);
+            }
+            if self.held {
+                ui.painter().rect_stroke(
+                    rect.shrink(self.size * 0.08),
+                    0.0,
+                    Stroke::new(self.size * 0.04, Color32::from_rgb(86, 180, 233)),
+                );
+            }
+            if self.selected {
+                // white in the dark theme and black in the light one, so it stands out from the
+                // panel around the cell
+                ui.painter().rect_stroke(
+                    rect,
+                    0.0,
+                    Stroke::new(self.size * 0.04, ui.visuals().strong_text_color()),
+                );
             }
         }
 
-        response
+        match token {
+            Some(token) => response.on_hover_text(Self::describe(token, self.checked)),
+            None => response,
+        }
+    }
+
+    // the tooltip: what the token is and does, which way it faces, and what Check found
+    fn describe(token: &Token, checked: Option<(bool, Option<bool>)>) -> String {
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        let purpose = match token.type_() {
+            TokenType::Laser => "Fires the beam from its front.",
+            TokenType::TargetMirror => {
+                "Reflects the beam off its mirror; the beam must hit its target to light it."
+            }
+            TokenType::BeamSplitter => {
+                "Reflects part of the beam and lets part pass straight through."
+            }
+            TokenType::DoubleMirror => "Reflects the beam off either side.",
+            TokenType::Checkpoint => "The beam must pass through it, between its walls.",
+            TokenType::CellBlocker => {
+                "Lets the beam through, but no other token can go in its cell."
+            }
+        };
+        let mut lines = vec![token.type_().to_string(), purpose.to_string()];
+        lines.push(match token.orientation() {
+            Some(orientation) => format!("Facing {:?}", orientation),
+            None => "Orientation unknown".to_string(),
+        });
+        if token.must_light() {
+            lines.push("Must be lit".to_string());
+        }
+        if let Some((lit, target_lit)) = checked {
+            lines.push(format!("Lit: {}", yes_no(lit)));
+            if let Some(target_lit) = target_lit {
+                lines.push(format!("Target lit: {}", yes_no(target_lit)));
+            }
+        }
+        lines.join("\n")
+    }
+
+    // colors from the Okabe-Ito palette, which stay distinguishable for colorblind users
+    fn token_tint(token_type: &TokenType) -> Color32 {
+        match token_type {
+            TokenType::Laser => Color32::from_rgb(213, 94, 0),
+            TokenType::TargetMirror => Color32::from_rgb(204, 121, 167),
+            TokenType::BeamSplitter => Color32::from_rgb(0, 158, 115),
+            TokenType::DoubleMirror => Color32::from_rgb(0, 114, 178),
+            TokenType::Checkpoint => Color32::from_rgb(240, 228, 66),
+            TokenType::CellBlocker => Color32::WHITE,
+        }
     }
 
     fn get_token_image(
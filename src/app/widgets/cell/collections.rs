@@ -2,14 +2,19 @@ use crate::{
     app::{resources::ImageBank, widgets::cell::Cell},
     solver::token::Token,
 };
+use eframe::egui::{vec2, Label};
 
 pub struct Bank {
     cell_size: f32,
+    tint_tokens: bool,
 }
 
 impl Bank {
-    pub fn new(cell_size: f32) -> Self {
-        Self { cell_size }
+    pub fn new(cell_size: f32, tint_tokens: bool) -> Self {
+        Self {
+            cell_size,
+            tint_tokens,
+        }
     }
 
     pub fn show(
@@ -17,13 +22,20 @@ impl Bank {
         ui: &mut eframe::egui::Ui,
         images: &ImageBank,
         tokens: &[Option<Token>; 11],
+        selected: Option<usize>,
+        held: Option<usize>,
     ) -> [eframe::egui::Response; 11] {
         let mut responses: Vec<eframe::egui::Response> = Vec::with_capacity(11);
         ui.horizontal(|ui| {
             for range in [0..3, 3..6, 6..9, 9..11] {
                 ui.vertical(|ui| {
                     for i in range {
-                        responses.push(Cell::new(self.cell_size).show(ui, images, &tokens[i]))
+                        responses.push(
+                            Cell::new(self.cell_size, self.tint_tokens)
+                                .selected(selected == Some(i))
+                                .held(held == Some(i))
+                                .show(ui, images, &tokens[i]),
+                        )
                     }
                 });
             }
@@ -35,13 +47,46 @@ impl Bank {
     }
 }
 
+// grid coordinates, as in "laser at C3": rows are lettered from the top, columns numbered from the left
+const ROW_LABELS: [&str; 5] = ["A", "B", "C", "D", "E"];
+const COLUMN_LABELS: [&str; 5] = ["1", "2", "3", "4", "5"];
+const ROW_LABEL_WIDTH: f32 = 12.0;
+
 pub struct Grid {
     cell_size: f32,
+    tint_tokens: bool,
+    // label the rows A-E and the columns 1-5
+    coordinate_labels: bool,
+    // after Check: the tokens as the checker left them, for each cell's tooltip
+    checked_cells: Option<[Option<Token>; 25]>,
+    // the cells the laser must not enter
+    forbidden: [bool; 25],
 }
 
 impl Grid {
-    pub fn new(cell_size: f32) -> Self {
-        Self { cell_size }
+    pub fn new(cell_size: f32, tint_tokens: bool) -> Self {
+        Self {
+            cell_size,
+            tint_tokens,
+            coordinate_labels: false,
+            checked_cells: None,
+            forbidden: [false; 25],
+        }
+    }
+
+    pub fn coordinate_labels(mut self, coordinate_labels: bool) -> Self {
+        self.coordinate_labels = coordinate_labels;
+        self
+    }
+
+    pub fn checked_cells(mut self, checked_cells: Option<[Option<Token>; 25]>) -> Self {
+        self.checked_cells = checked_cells;
+        self
+    }
+
+    pub fn forbidden(mut self, forbidden: [bool; 25]) -> Self {
+        self.forbidden = forbidden;
+        self
     }
 
     pub fn show(
@@ -49,13 +94,46 @@ impl Grid {
         ui: &mut eframe::egui::Ui,
         images: &ImageBank,
         tokens: &[Option<Token>; 25],
+        waypoints: &[bool; 25],
+        selected: Option<usize>,
+        held: Option<usize>,
     ) -> [eframe::egui::Response; 25] {
         let mut responses: Vec<eframe::egui::Response> = Vec::with_capacity(11);
         ui.vertical(|ui| {
-            for range in [0..5, 5..10, 10..15, 15..20, 20..25] {
+            if self.coordinate_labels {
+                ui.horizontal(|ui| {
+                    let label_height = ui.spacing().interact_size.y;
+                    ui.add_sized(vec2(ROW_LABEL_WIDTH, label_height), Label::new(""));
+                    for column_label in COLUMN_LABELS {
+                        ui.add_sized(vec2(self.cell_size, label_height), Label::new(column_label));
+                    }
+                });
+            }
+            for (row, range) in [0..5, 5..10, 10..15, 15..20, 20..25]
+                .into_iter()
+                .enumerate()
+            {
                 ui.horizontal(|ui| {
+                    if self.coordinate_labels {
+                        ui.add_sized(
+                            vec2(ROW_LABEL_WIDTH, self.cell_size),
+                            Label::new(ROW_LABELS[row]),
+                        );
+                    }
                     for i in range {
-                        responses.push(Cell::new(self.cell_size).show(ui, images, &tokens[i]))
+                        responses.push(
+                            Cell::new(self.cell_size, self.tint_tokens)
+                                .selected(selected == Some(i))
+                                .held(held == Some(i))
+                                .waypoint(waypoints[i])
+                                .forbidden(self.forbidden[i])
+                                .checked(
+                                    self.checked_cells
+                                        .as_ref()
+                                        .and_then(|cells| cells[i].as_ref()),
+                                )
+                                .show(ui, images, &tokens[i]),
+                        )
                     }
                 });
             }
@@ -69,11 +147,15 @@ impl Grid {
 
 pub struct ToBeAdded {
     cell_size: f32,
+    tint_tokens: bool,
 }
 
 impl ToBeAdded {
-    pub fn new(cell_size: f32) -> Self {
-        Self { cell_size }
+    pub fn new(cell_size: f32, tint_tokens: bool) -> Self {
+        Self {
+            cell_size,
+            tint_tokens,
+        }
     }
 
     #[allow(clippy::needless_range_loop)]
@@ -82,11 +164,18 @@ impl ToBeAdded {
         ui: &mut eframe::egui::Ui,
         images: &ImageBank,
         tokens: &[Option<Token>; 6],
+        selected: Option<usize>,
+        held: Option<usize>,
     ) -> [eframe::egui::Response; 6] {
         let mut responses: Vec<eframe::egui::Response> = Vec::with_capacity(6);
         ui.horizontal(|ui| {
             for i in 0..6 {
-                responses.push(Cell::new(self.cell_size).show(ui, images, &tokens[i]))
+                responses.push(
+                    Cell::new(self.cell_size, self.tint_tokens)
+                        .selected(selected == Some(i))
+                        .held(held == Some(i))
+                        .show(ui, images, &tokens[i]),
+                )
             }
         });
 
@@ -8,6 +8,9 @@ use std::time::Duration;
 use tokio::runtime::Runtime;
 
 fn main() {
+    #[cfg(feature = "logging")]
+    env_logger::init();
+
     let rt = Runtime::new().expect("failed to make new Tokio Runtime");
 
     let _enter = rt.enter();
@@ -29,7 +32,7 @@ fn main() {
             initial_window_size: Some(eframe::egui::vec2(1100., 900.)),
             ..Default::default()
         },
-        Box::new(|_cc| Box::<app::MyApp>::default()),
+        Box::new(|cc| Box::new(app::MyApp::new(cc))),
     )
     .expect("Failed to launch app");
 }
@@ -1,28 +1,91 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::Stream;
 
 pub mod orientation;
 
 pub mod token;
-use token::{Token, TokenType};
+use orientation::Orientation;
+use token::{MirrorRules, Token, TokenType};
+
+pub mod puzzle_def;
+
+pub mod board;
+#[allow(unused_imports)]
+pub use board::Board;
+
+pub mod search_tree;
+use search_tree::SearchTree;
 
 mod solver_node;
 use crate::solver::token::TOKEN_TYPES;
 use solver_node::SolverNode;
+#[allow(unused_imports)]
+pub use solver_node::{cell_region, CellRegion, PlacementOrder};
 
 mod checker;
+pub use checker::{CheckError, Checker};
+
+// how many nodes .solve_stream() searches between NodeExpanded events
+const PROGRESS_INTERVAL: usize = 1000;
+
+/// SolveEvent: progress of a solver running in the background; see .solve_stream()
+#[derive(Clone, Debug)]
+pub enum SolveEvent {
+    // the number of nodes searched so far; sent every PROGRESS_INTERVAL nodes
+    NodeExpanded(usize),
+    SolutionFound([Option<Token>; 25]),
+    // every node was searched without finding a solution
+    Exhausted,
+    // the puzzle is invalid, or max_nodes was reached
+    Error(String),
+}
+
+/// Recheck: what .recheck() did with a board changed in one cell
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recheck {
+    // only the token's orientation changed, so the board was checked without a search; whether
+    // it's still solved
+    Checked(bool),
+    // a token was placed, moved or removed, so the puzzle was searched again; its solution, if any
+    Searched(Option<[Option<Token>; 25]>),
+}
 
 /// LaserMazeSolver: main struct. initialize this with the puzzle -> run .solve()
 /// initial_grid_config: initially, where the tokens are placed on the grid and their rotation
 /// tokens_to_be_added: the "add to grid" section of the card
-/// dfs_stack: an Arc<Mutex<SolverNode>>> that holds the thread-safe stack used by DFS algorithm
+/// stack: the SolverNodes left to search by the DFS algorithm. The solver is Send; wrap it in a
+///     SharedSolver to share it across threads
+/// lit_solution: the solution found by the last call to .solve(), with the lit state from tracing the laser
+/// waypoints: cells the laser must pass through, in the same coordinates as initial_grid_config
+/// forbidden_cells: cells the laser must not enter, in the same coordinates as initial_grid_config
+/// mirror_rules: how the double mirror redirects the laser; the official rules by default
+/// placement_order: the order the empty cells are tried in when placing tokens; the spiral by default
+/// rng: if Some, the branches of each node are searched in a random order; None (default) searches
+///     them in a fixed order. Seed it (StdRng::seed_from_u64) for reproducible random orders
+/// max_nodes: if Some(n), .solve() gives up with Err after popping n SolverNodes; None (default) is unbounded
 pub struct LaserMazeSolver {
     initial_grid_config: [Option<Token>; 25],
     tokens_to_be_added: Vec<Token>,
     pub stack: Vec<SolverNode>,
     targets: u8,
+    lit_solution: Option<[Option<Token>; 25]>,
+    waypoints: Vec<usize>,
+    forbidden_cells: Vec<usize>,
+    mirror_rules: MirrorRules,
+    placement_order: PlacementOrder,
+    pub max_nodes: Option<usize>,
+    pub rng: Option<StdRng>,
 }
 
 impl LaserMazeSolver {
+    /// Doesn't validate the puzzle; .solve() does. Use this when the puzzle may still be
+    /// incomplete, like the GUI does while the user is setting up the board
     #[allow(dead_code)]
     pub fn new(
         initial_grid_config: [Option<Token>; 25],
@@ -39,39 +102,93 @@ impl LaserMazeSolver {
             tokens_to_be_added,
             targets,
             stack: vec![initial_solver_node],
+            lit_solution: None,
+            waypoints: vec![],
+            forbidden_cells: vec![],
+            mirror_rules: MirrorRules::default(),
+            placement_order: PlacementOrder::default(),
+            max_nodes: None,
+            rng: None,
         }
     }
 
-    /// validate that a good Challenge is provided
-    fn validate(&self) -> Result<(), String> {
-        // 1 - 3 targets
-        if (self.targets == 0) || (self.targets > 3) {
-            return Err(String::from("Invalid number of targets!"));
+    /// Like ::new(), but runs .validate() immediately, so an invalid puzzle is rejected at
+    /// construction. Prefer this when loading finished puzzles, e.g. from a file
+    #[allow(dead_code)]
+    pub fn try_new(
+        initial_grid_config: [Option<Token>; 25],
+        tokens_to_be_added: Vec<Token>,
+        targets: u8,
+    ) -> Result<Self, String> {
+        let solver = Self::new(initial_grid_config, tokens_to_be_added, targets);
+        solver.validate()?;
+        Ok(solver)
+    }
+
+    /// Require the laser to pass through each of `waypoints` (cell indices) in a solution
+    #[allow(dead_code)]
+    pub fn with_waypoints(mut self, waypoints: Vec<usize>) -> Self {
+        for node in self.stack.iter_mut() {
+            node.waypoints.clone_from(&waypoints);
+        }
+        self.waypoints = waypoints;
+        self
+    }
+
+    /// Reject any solution whose laser enters one of `forbidden_cells` (cell indices)
+    #[allow(dead_code)]
+    pub fn with_forbidden_cells(mut self, forbidden_cells: Vec<usize>) -> Self {
+        for node in self.stack.iter_mut() {
+            node.forbidden_cells.clone_from(&forbidden_cells);
         }
+        self.forbidden_cells = forbidden_cells;
+        self
+    }
 
-        // make sure count of each type of Token is valid
-        // count piece types on the grid
-        let mut token_counts: HashMap<TokenType, u8> = HashMap::new();
-        // Initialize each token count with 0
-        for token in TOKEN_TYPES.iter() {
-            token_counts.entry(*token).or_insert(0);
+    /// Solve under `mirror_rules` instead of the official rules
+    #[allow(dead_code)]
+    pub fn with_mirror_rules(mut self, mirror_rules: MirrorRules) -> Self {
+        for node in self.stack.iter_mut() {
+            node.mirror_rules = mirror_rules.clone();
         }
-        for token in self.initial_grid_config.iter().flatten() {
-            token_counts
-                .entry(*token.type_())
-                .and_modify(|counter| *counter += 1)
-                .or_insert(1);
+        self.mirror_rules = mirror_rules;
+        self
+    }
+
+    /// Try the empty cells in `placement_order` instead of the spiral
+    #[allow(dead_code)]
+    pub fn with_placement_order(mut self, placement_order: PlacementOrder) -> Self {
+        for node in self.stack.iter_mut() {
+            node.placement_order = placement_order;
         }
-        // count pieces to be added
-        for token in &self.tokens_to_be_added {
-            token_counts
-                .entry(*token.type_())
-                .and_modify(|counter| *counter += 1)
-                .or_insert(1);
+        self.placement_order = placement_order;
+        self
+    }
+
+    /// validate that a good Challenge is provided, returning the first problem found
+    pub fn validate(&self) -> Result<(), String> {
+        match self.validate_all().into_iter().next() {
+            Some(problem) => Err(problem),
+            None => Ok(()),
+        }
+    }
+
+    /// Every problem with the Challenge, rather than just the first; empty if it's valid
+    pub fn validate_all(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        // 1 - 3 targets
+        if (self.targets == 0) || (self.targets > 3) {
+            problems.push(String::from("Invalid number of targets!"));
         }
-        // check the counts
-        for (token_type, count) in token_counts {
+
+        // make sure count of each type of Token is valid. go through the types in a fixed order so
+        // the problems are always listed the same way
+        let token_counts = token_counts(&self.initial_grid_config, &self.tokens_to_be_added);
+        for token_type in TOKEN_TYPES.iter() {
+            let count = token_counts[token_type];
             let (min_count, max_count) = match token_type {
+                // exactly one laser, on the grid or to be added; SolverNode's laser placement relies on it
                 TokenType::Laser => (1, 1),
                 TokenType::TargetMirror => (1, 5),
                 TokenType::BeamSplitter => (0, 2), // previously I thought `n_targets = 1 + n_beam_splitters`, but bonus challenge 98, 99 contracdict this (self.targets - 1, self.targets - 1),
@@ -80,7 +197,7 @@ impl LaserMazeSolver {
                 TokenType::CellBlocker => (0, 1),
             };
             if (count < min_count) || (count > max_count) {
-                return Err(format!(
+                problems.push(format!(
                     "Invalid piece count for piece type {:?}!",
                     token_type
                 ));
@@ -96,7 +213,27 @@ impl LaserMazeSolver {
             .map(|token| token.must_light() as u8)
             .sum();
         if self.targets < must_light_count {
-            return Err(String::from("Invalid number of pieces which must be lit!"));
+            problems.push(String::from("Invalid number of pieces which must be lit!"));
+        }
+
+        if self.waypoints.iter().any(|cell_index| *cell_index > 24) {
+            problems.push(String::from("Invalid waypoint cell index!"));
+        }
+
+        if self
+            .forbidden_cells
+            .iter()
+            .any(|cell_index| *cell_index > 24)
+        {
+            problems.push(String::from("Invalid forbidden cell index!"));
+        }
+
+        if self
+            .waypoints
+            .iter()
+            .any(|cell_index| self.forbidden_cells.contains(cell_index))
+        {
+            problems.push(String::from("A waypoint can't also be a forbidden cell!"));
         }
 
         // no cell blocker in tokens to be added
@@ -105,253 +242,2036 @@ impl LaserMazeSolver {
             .iter()
             .any(|token| token.type_() == &TokenType::CellBlocker)
         {
-            return Err(String::from("Cell Blocker included in tokens_to_be_added!"));
+            problems.push(String::from("Cell Blocker included in tokens_to_be_added!"));
+        }
+
+        problems
+    }
+
+    #[allow(dead_code)]
+    pub fn solve(&mut self) -> Result<Option<[Option<Token>; 25]>, String> {
+        self.solve_with_progress(|_| true)
+    }
+
+    /// Like .solve(), but returns the Checker for the solution, traced with the beam path recorded:
+    /// the solved cells, which tokens are lit, and the route of the beam, all in one
+    #[allow(dead_code)]
+    pub fn solve_detailed(&mut self) -> Result<Option<Checker>, String> {
+        let cells = match self.solve()? {
+            Some(cells) => cells,
+            None => return Ok(None),
+        };
+        let mut node = SolverNode::new(cells, vec![], self.targets);
+        node.waypoints.clone_from(&self.waypoints);
+        node.forbidden_cells.clone_from(&self.forbidden_cells);
+        node.mirror_rules = self.mirror_rules.clone();
+        let checker = Checker::from_solver_node(node)
+            .recording_beam_path()
+            .check()
+            .map_err(|e| e.to_string())?;
+        Ok(Some(checker))
+    }
+
+    /// Run .solve() on the tokio runtime's blocking thread pool, reporting progress as a stream of
+    /// SolveEvents. The last event is SolutionFound, Exhausted or Error.
+    /// Must be called from within the tokio runtime (see main.rs)
+    #[allow(dead_code)]
+    pub fn solve_stream(self) -> impl Stream<Item = SolveEvent> {
+        UnboundedReceiverStream::new(self.solve_channel())
+    }
+
+    /// Like .solve_stream(), but returns the receiving end of the channel, so the GUI can poll it
+    /// each frame with try_recv() without blocking. Dropping the receiver stops the solver
+    pub fn solve_channel(mut self) -> UnboundedReceiver<SolveEvent> {
+        let (sender, receiver) = unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            let result = self.solve_with_progress(|nodes_searched| {
+                sender
+                    .send(SolveEvent::NodeExpanded(nodes_searched))
+                    .is_ok()
+            });
+            let event = match result {
+                Ok(Some(cells)) => SolveEvent::SolutionFound(cells),
+                Ok(None) => SolveEvent::Exhausted,
+                Err(s) => SolveEvent::Error(s),
+            };
+            // if the receiver was dropped, nobody is waiting for the result
+            let _ = sender.send(event);
+        });
+        receiver
+    }
+
+    // `progress` is called with the number of nodes searched every PROGRESS_INTERVAL nodes;
+    // returning false stops the search with Err
+    fn solve_with_progress(
+        &mut self,
+        progress: impl FnMut(usize) -> bool,
+    ) -> Result<Option<[Option<Token>; 25]>, String> {
+        self.search(progress, None)
+    }
+
+    /// Like .solve(), but records every node searched until the first solution, with the node it
+    /// branched from. Every node's board is kept, so this is only practical for tiny puzzles; it's
+    /// for debugging how the solver branches on them
+    #[allow(dead_code)]
+    pub fn solve_trace(&mut self) -> Result<SearchTree, String> {
+        let mut tree = SearchTree::default();
+        self.search(|_| true, Some(&mut tree))?;
+        Ok(tree)
+    }
+
+    // the depth first search behind .solve_with_progress() and .solve_trace(); each node searched is
+    // added to `trace`, if it's Some
+    fn search(
+        &mut self,
+        mut progress: impl FnMut(usize) -> bool,
+        mut trace: Option<&mut SearchTree>,
+    ) -> Result<Option<[Option<Token>; 25]>, String> {
+        // Returns Ok(Some(_)) if solution found, Ok(None) if no solution, Err(s) if
+        // invalid puzzle provided; s describes why the puzzle is invalid
+        // The tokens in the solution are reset (see Token::reset); use .solution_with_lit_state()
+        // for the tokens as lit by the laser
+        // Tokens in initial_grid_config which already have an orientation keep it; only unoriented
+        // tokens are rotated
+        // Every token in tokens_to_be_added is on the board in a returned solution; a board which
+        // lights the targets while tokens are left over is not solved (see Checker::solved)
+        // Returns Err(s) if max_nodes is set and that many nodes were searched without a solution
+        self.validate()?;
+
+        // when tracing, the index in the trace of the node each node on the stack branched from
+        let mut parents: Vec<Option<usize>> = match trace {
+            Some(_) => vec![None; self.stack.len()],
+            None => vec![],
+        };
+        let mut nodes_searched: usize = 0;
+        while let Some(mut node) = self.stack.pop() {
+            let parent = parents.pop().flatten();
+            if self
+                .max_nodes
+                .is_some_and(|max_nodes| nodes_searched >= max_nodes)
+            {
+                log::debug!("Gave up after searching {} nodes", nodes_searched);
+                return Err(format!(
+                    "Gave up after searching {} nodes without a solution!",
+                    nodes_searched
+                ));
+            }
+            nodes_searched += 1;
+            if nodes_searched.is_multiple_of(PROGRESS_INTERVAL) && !progress(nodes_searched) {
+                return Err(String::from("Solver was cancelled!"));
+            }
+
+            let traced_index = trace.as_mut().map(|tree| {
+                let tokens_to_be_added = node
+                    .tokens_to_be_added
+                    .iter()
+                    .chain(node.tokens_to_be_added_shuffled.iter())
+                    .cloned()
+                    .collect();
+                tree.push(parent, node.cells.clone(), tokens_to_be_added)
+            });
+            match node.generate_branches() {
+                Ok(cells) => {
+                    if let (Some(tree), Some(index)) = (trace.as_mut(), traced_index) {
+                        tree.nodes[index].solution = true;
+                    }
+                    debug_assert!(
+                        self.unplaced_tokens(&cells).is_empty(),
+                        "The solver returned a solution with tokens left to be added"
+                    );
+                    log::debug!("Found a solution after searching {} nodes", nodes_searched);
+                    // the returned cells have been reset; trace the laser again to keep a lit copy
+                    let mut lit_node = SolverNode::new(cells.clone(), vec![], self.targets);
+                    lit_node.mirror_rules = self.mirror_rules.clone();
+                    self.lit_solution = Some(
+                        lit_node
+                            .check()
+                            .expect("A solution has an oriented laser")
+                            .cells()
+                            .clone(),
+                    );
+                    return Ok(Some(cells));
+                }
+                Err(mut new_nodes) => {
+                    if let Some(rng) = self.rng.as_mut() {
+                        new_nodes.shuffle(rng);
+                    }
+                    if traced_index.is_some() {
+                        parents.resize(parents.len() + new_nodes.len(), traced_index);
+                    }
+                    self.stack.extend(new_nodes)
+                }
+            }
+        }
+
+        log::debug!("No solution after searching {} nodes", nodes_searched);
+        Ok(None)
+    }
+
+    /// Keep searching after the first solution, and return every distinct solution.
+    /// Symmetric tokens are rotated to their canonical orientation (see Token::canonicalize_orientation),
+    /// so solutions which only differ by a 180 degree rotation of a symmetric token count once.
+    /// Returns Err(s) for an invalid puzzle, or if max_nodes is set and that many nodes were searched
+    #[allow(dead_code)]
+    pub fn solve_all(&mut self) -> Result<Vec<[Option<Token>; 25]>, String> {
+        self.validate()?;
+
+        let mut solutions: Vec<[Option<Token>; 25]> = vec![];
+        let mut nodes_searched: usize = 0;
+        while let Some(mut node) = self.stack.pop() {
+            if self
+                .max_nodes
+                .is_some_and(|max_nodes| nodes_searched >= max_nodes)
+            {
+                return Err(format!(
+                    "Gave up after searching {} nodes for all solutions!",
+                    nodes_searched
+                ));
+            }
+            nodes_searched += 1;
+
+            match node.generate_branches() {
+                Ok(mut cells) => {
+                    cells
+                        .iter_mut()
+                        .flatten()
+                        .for_each(|token| token.canonicalize_orientation());
+                    if !solutions.contains(&cells) {
+                        solutions.push(cells);
+                    }
+                }
+                Err(mut new_nodes) => {
+                    if let Some(rng) = self.rng.as_mut() {
+                        new_nodes.shuffle(rng);
+                    }
+                    self.stack.extend(new_nodes)
+                }
+            }
+        }
+
+        Ok(solutions)
+    }
+
+    /// The types of the tokens to be added which the puzzle doesn't need: it still solves with that one
+    /// token left out. A well made puzzle has none. Each token is tried with its own solve, using the
+    /// same waypoints, mirror rules and max_nodes. Returns Err(s) for an invalid puzzle, or if a solve
+    /// gives up after max_nodes
+    #[allow(dead_code)]
+    pub fn redundant_tokens(&mut self) -> Result<Vec<TokenType>, String> {
+        self.validate()?;
+
+        let mut result = vec![];
+        // identical tokens are only solved without once
+        let mut tried: Vec<(Token, bool)> = vec![];
+        for (i, token) in self.tokens_to_be_added.iter().enumerate() {
+            let redundant = match tried.iter().find(|(tried_token, _)| tried_token == token) {
+                Some((_, redundant)) => *redundant,
+                None => {
+                    let mut tokens_to_be_added = self.tokens_to_be_added.clone();
+                    tokens_to_be_added.remove(i);
+                    let mut solver =
+                        self.with_board(self.initial_grid_config.clone(), tokens_to_be_added);
+                    // without some tokens, e.g. the laser, the puzzle isn't valid; they're needed
+                    let redundant = solver.validate().is_ok() && solver.solve()?.is_some();
+                    tried.push((token.clone(), redundant));
+                    redundant
+                }
+            };
+            if redundant {
+                result.push(*token.type_());
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Like .solve(), but for puzzle variants which don't have to use every token to be added: the
+    /// solution placing the fewest of them. Each distinct subset of the tokens to be added is solved
+    /// with its own solve, smallest first, and the first solution found is returned, so no solution
+    /// places fewer tokens. Returns Err(s) for an invalid puzzle, or if a solve gives up after max_nodes
+    #[allow(dead_code)]
+    pub fn solve_minimal_pieces(&mut self) -> Result<Option<[Option<Token>; 25]>, String> {
+        self.validate()?;
+
+        let token_count = self.tokens_to_be_added.len();
+        let mut tried: Vec<Vec<Token>> = vec![];
+        for subset_size in 0..=token_count {
+            for subset in 0..(1u32 << token_count) {
+                if subset.count_ones() as usize != subset_size {
+                    continue;
+                }
+                let tokens_to_be_added: Vec<Token> = self
+                    .tokens_to_be_added
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| subset & (1 << i) != 0)
+                    .map(|(_, token)| token.clone())
+                    .collect();
+                // identical tokens make the same subset more than once
+                if tried.contains(&tokens_to_be_added) {
+                    continue;
+                }
+                tried.push(tokens_to_be_added.clone());
+                let mut solver =
+                    self.with_board(self.initial_grid_config.clone(), tokens_to_be_added);
+                // without some tokens, e.g. the laser, the puzzle isn't valid
+                if solver.validate().is_err() {
+                    continue;
+                }
+                if let Some(solution) = solver.solve()? {
+                    return Ok(Some(solution));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// After a change to one cell of a solved board: `solved` is the board as solved, and `cells` is
+    /// the same board with `cell_index` changed. If the token in that cell was only turned, the board
+    /// is just checked, with no search. Otherwise a token was placed, moved or removed, and the puzzle
+    /// is solved again from its initial grid. Returns Err(s) if `cells` differs from `solved` in
+    /// another cell, if the check fails (e.g. the turned token is unoriented), or if the solve fails
+    #[allow(dead_code)]
+    pub fn recheck(
+        &mut self,
+        solved: &[Option<Token>; 25],
+        cells: &[Option<Token>; 25],
+        cell_index: usize,
+    ) -> Result<Recheck, String> {
+        if let Some(other) = (0..25).find(|i| *i != cell_index && solved[*i] != cells[*i]) {
+            return Err(format!("{} changed too!", cell_name(other)));
+        }
+
+        match (&solved[cell_index], &cells[cell_index]) {
+            (Some(before), Some(after))
+                if before.type_() == after.type_() && before.must_light() == after.must_light() =>
+            {
+                check_board_with_unplaced(
+                    cells.clone(),
+                    &[],
+                    self.targets,
+                    &self.waypoints,
+                    &self.forbidden_cells,
+                    &self.mirror_rules,
+                )
+                .map(Recheck::Checked)
+                .map_err(|e| e.to_string())
+            }
+            _ => {
+                let mut solver = self.with_board(
+                    self.initial_grid_config.clone(),
+                    self.tokens_to_be_added.clone(),
+                );
+                Ok(Recheck::Searched(solver.solve()?))
+            }
         }
+    }
 
-        Ok(())
+    /// For a token on the grid which isn't oriented yet, the orientations which still lead to a solution.
+    /// Symmetric tokens only list their canonical orientations (e.g. North, not South, for a beam
+    /// splitter). Returns Err(s) for an invalid puzzle, if there's no unoriented token in `cell_index`,
+    /// or if a solve gives up after max_nodes
+    #[allow(dead_code)]
+    pub fn solving_orientations(&mut self, cell_index: usize) -> Result<Vec<Orientation>, String> {
+        self.validate()?;
+        let token = match self.initial_grid_config.get(cell_index) {
+            Some(Some(token)) if token.orientation().is_none() => token,
+            _ => return Err(String::from("There is no unoriented token in that cell!")),
+        };
+
+        let mut result = vec![];
+        let mut node = SolverNode::new(
+            self.initial_grid_config.clone(),
+            self.tokens_to_be_added.clone(),
+            self.targets,
+        );
+        node.waypoints.clone_from(&self.waypoints);
+        node.forbidden_cells.clone_from(&self.forbidden_cells);
+        for orientation_index in node.orientation_iter(token.type_(), cell_index) {
+            let orientation = Orientation::from_index(orientation_index);
+            let mut cells = self.initial_grid_config.clone();
+            if let Some(token) = cells[cell_index].as_mut() {
+                token.orientation = Some(orientation.clone());
+            }
+            if self
+                .with_board(cells, self.tokens_to_be_added.clone())
+                .solve()?
+                .is_some()
+            {
+                result.push(orientation);
+            }
+        }
+
+        Ok(result)
+    }
+
+    // a solver for another board, with the same targets, waypoints, forbidden cells, mirror rules,
+    // placement order, max_nodes and rng
+    fn with_board(
+        &self,
+        initial_grid_config: [Option<Token>; 25],
+        tokens_to_be_added: Vec<Token>,
+    ) -> Self {
+        let mut solver =
+            LaserMazeSolver::new(initial_grid_config, tokens_to_be_added, self.targets)
+                .with_waypoints(self.waypoints.clone())
+                .with_forbidden_cells(self.forbidden_cells.clone())
+                .with_mirror_rules(self.mirror_rules.clone())
+                .with_placement_order(self.placement_order);
+        solver.max_nodes = self.max_nodes;
+        solver.rng.clone_from(&self.rng);
+        solver
+    }
+
+    /// The solution from the last successful call to .solve(), with each token's `lit` and
+    /// `target_lit` state as left by tracing the laser through the solved board.
+    /// .solve() itself returns the tokens reset, as if the laser were off.
+    #[allow(dead_code)]
+    pub fn solution_with_lit_state(&self) -> Option<&[Option<Token>; 25]> {
+        self.lit_solution.as_ref()
+    }
+
+    /// Given the board the player is working on (`current_grid`, in the same coordinates as
+    /// `initial_grid_config`), which of `tokens_to_be_added` are still not on the board?
+    /// Tokens are matched by type and whether they must be lit; which cell they're in doesn't matter
+    pub fn unplaced_tokens(&self, current_grid: &[Option<Token>; 25]) -> Vec<Token> {
+        // count the tokens the player has placed, by removing the tokens that started on the grid
+        let mut placed_counts: HashMap<(TokenType, bool), usize> = HashMap::new();
+        for token in current_grid.iter().flatten() {
+            *placed_counts
+                .entry((*token.type_(), token.must_light()))
+                .or_insert(0) += 1;
+        }
+        for token in self.initial_grid_config.iter().flatten() {
+            if let Some(count) = placed_counts.get_mut(&(*token.type_(), token.must_light())) {
+                *count = count.saturating_sub(1);
+            }
+        }
+
+        let mut result = vec![];
+        for token in &self.tokens_to_be_added {
+            match placed_counts.get_mut(&(*token.type_(), token.must_light())) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => result.push(token.clone()),
+            }
+        }
+        result
+    }
+}
+
+/// SharedSolver: a LaserMazeSolver which can be cloned and shared across threads. The clones share
+/// one solver, so one thread at a time can use it
+#[allow(dead_code)]
+#[derive(Clone)]
+pub struct SharedSolver(Arc<Mutex<LaserMazeSolver>>);
+
+#[allow(dead_code)]
+impl SharedSolver {
+    pub fn new(solver: LaserMazeSolver) -> Self {
+        Self(Arc::new(Mutex::new(solver)))
+    }
+
+    /// The solver, for as long as the guard is held. A thread which panicked while holding it
+    /// doesn't lock the solver out for the others
+    pub fn lock(&self) -> MutexGuard<'_, LaserMazeSolver> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits for any other thread using the solver, then runs .solve()
+    pub fn solve(&self) -> Result<Option<[Option<Token>; 25]>, String> {
+        self.lock().solve()
+    }
+}
+
+impl From<LaserMazeSolver> for SharedSolver {
+    fn from(solver: LaserMazeSolver) -> Self {
+        Self::new(solver)
+    }
+}
+
+/// Check whether a fully specified board lights `targets` targets, passes through each of
+/// `waypoints`, stays out of each of `forbidden_cells`, and satisfies the other rules of the puzzle,
+/// with the double mirror following `mirror_rules`. Returns Err, instead of panicking, if any token
+/// on the board is unoriented or there is no laser
+#[allow(dead_code)]
+pub fn check_board(
+    cells: [Option<Token>; 25],
+    targets: u8,
+    waypoints: &[usize],
+    forbidden_cells: &[usize],
+    mirror_rules: &MirrorRules,
+) -> Result<bool, CheckError> {
+    check_board_with_unplaced(
+        cells,
+        &[],
+        targets,
+        waypoints,
+        forbidden_cells,
+        mirror_rules,
+    )
+}
+
+/// Like check_board(), but with `unplaced` tokens still to be added. Like the solver, a board with
+/// tokens left to be added isn't solved, even if the laser lights the targets
+pub fn check_board_with_unplaced(
+    cells: [Option<Token>; 25],
+    unplaced: &[Token],
+    targets: u8,
+    waypoints: &[usize],
+    forbidden_cells: &[usize],
+    mirror_rules: &MirrorRules,
+) -> Result<bool, CheckError> {
+    if let Some(cell_index) = cells.iter().position(|cell| {
+        cell.as_ref()
+            .is_some_and(|token| token.orientation().is_none())
+    }) {
+        return Err(CheckError::UnorientedToken(cell_index));
+    }
+    let mut node = SolverNode::new(cells, unplaced.to_vec(), targets);
+    node.waypoints = waypoints.to_vec();
+    node.forbidden_cells = forbidden_cells.to_vec();
+    node.mirror_rules = mirror_rules.clone();
+    Ok(Checker::from_solver_node(node).check()?.solved())
+}
+
+/// Whether every token on the board is oriented, so the board can be checked
+pub fn is_fully_oriented(cells: &[Option<Token>; 25]) -> bool {
+    cells
+        .iter()
+        .flatten()
+        .all(|token| token.orientation().is_some())
+}
+
+/// Where the laser is on the board, and which way it faces (None if it isn't oriented). None if there
+/// is no laser on the board
+pub fn laser_state(cells: &[Option<Token>; 25]) -> Option<(usize, Option<Orientation>)> {
+    cells
+        .iter()
+        .enumerate()
+        .find_map(|(cell_index, cell)| match cell {
+            Some(token) if token.type_() == &TokenType::Laser => {
+                Some((cell_index, token.orientation().cloned()))
+            }
+            _ => None,
+        })
+}
+
+/// How many of each type of token the puzzle uses, on the grid and to be added. Every type has an
+/// entry, 0 if it isn't used
+pub fn token_counts(cells: &[Option<Token>; 25], to_be_added: &[Token]) -> HashMap<TokenType, u8> {
+    let mut token_counts: HashMap<TokenType, u8> = HashMap::new();
+    // Initialize each token count with 0
+    for token in TOKEN_TYPES.iter() {
+        token_counts.entry(*token).or_insert(0);
+    }
+    for token in cells.iter().flatten().chain(to_be_added) {
+        token_counts
+            .entry(*token.type_())
+            .and_modify(|counter| *counter += 1)
+            .or_insert(1);
+    }
+    token_counts
+}
+
+/// The fewest and most targets the puzzle can ask for: at least 1 and every target mirror which must
+/// be lit, and at most 3 and every target mirror, on the grid or to be added
+pub fn targets_bounds(cells: &[Option<Token>; 25], to_be_added: &[Token]) -> (u8, u8) {
+    let target_mirrors = cells
+        .iter()
+        .flatten()
+        .chain(to_be_added)
+        .filter(|token| token.type_() == &TokenType::TargetMirror);
+    let must_light = target_mirrors
+        .clone()
+        .filter(|token| token.must_light())
+        .count() as u8;
+    (must_light.max(1), (target_mirrors.count() as u8).min(3))
+}
+
+/// A guess at how many targets the puzzle asks for, for when it isn't written down: one for each
+/// target mirror which must be lit, or 1 if none must be
+pub fn suggested_targets(cells: &[Option<Token>; 25], to_be_added: &[Token]) -> u8 {
+    targets_bounds(cells, to_be_added).0.min(3)
+}
+
+/// Whether the token at `cell_index` may face `orientation`: lasers, checkpoints, and targets which must
+/// be lit can't point off the board (or into a cell blocker on the edge). True for an empty cell
+pub fn orientation_is_legal(
+    cells: &[Option<Token>; 25],
+    cell_index: usize,
+    orientation: &Orientation,
+) -> bool {
+    SolverNode::new(cells.clone(), vec![], 0).orientation_is_legal(cell_index, orientation)
+}
+
+/// The beam's route across the board, from beam_path()
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeamPath {
+    /// The cells holding a token which the beam enters, in the order it reaches them, each with the
+    /// direction the beam is travelling
+    pub steps: Vec<(usize, Orientation)>,
+    /// For each cell, the directions the beam crosses it in or leaves it in, indexed by
+    /// Orientation::to_index(). After a beam splitter a cell can hold more than one
+    pub directions: [[bool; 4]; 25],
+}
+
+impl BeamPath {
+    /// The directions the beam takes through `cell_index`
+    pub fn directions_at(&self, cell_index: usize) -> Vec<Orientation> {
+        (0..4)
+            .filter(|&i| self.directions[cell_index][i])
+            .map(Orientation::from_index)
+            .collect()
+    }
+
+    /// Whether beams cross `cell_index` both north-south and east-west
+    pub fn beams_intersect_at(&self, cell_index: usize) -> bool {
+        let [north, east, south, west] = self.directions[cell_index];
+        (north || south) && (east || west)
+    }
+}
+
+/// The beam's route across `cells`. Returns Err if any token on the board is unoriented or there is no laser
+pub fn beam_path(
+    cells: &[Option<Token>; 25],
+    mirror_rules: &MirrorRules,
+) -> Result<BeamPath, CheckError> {
+    let checker = trace_beam(cells, None, mirror_rules)?;
+    Ok(BeamPath {
+        steps: checker.beam_path().to_vec(),
+        directions: *checker.laser_visited(),
+    })
+}
+
+/// Like beam_path(), but only as far as the beam gets before it first goes wrong, by leaving the
+/// board or hitting a side of a token which rejects it. A hint which shows where the beam fails,
+/// without showing the way to the targets. The whole route if the beam never goes wrong
+#[allow(dead_code)]
+pub fn beam_path_until_failure(
+    cells: &[Option<Token>; 25],
+    mirror_rules: &MirrorRules,
+) -> Result<BeamPath, CheckError> {
+    let checker = trace_beam(cells, None, mirror_rules)?;
+    let checker = match checker.failed_at_step() {
+        Some(steps) => trace_beam(cells, Some(steps), mirror_rules)?,
+        None => checker,
+    };
+    Ok(BeamPath {
+        steps: checker.beam_path().to_vec(),
+        directions: *checker.laser_visited(),
+    })
+}
+
+// the checker after tracing the beam across `cells`, recording its path, for at most `steps` steps
+fn trace_beam(
+    cells: &[Option<Token>; 25],
+    steps: Option<usize>,
+    mirror_rules: &MirrorRules,
+) -> Result<Checker, CheckError> {
+    if let Some(cell_index) = cells.iter().position(|cell| {
+        cell.as_ref()
+            .is_some_and(|token| token.orientation().is_none())
+    }) {
+        return Err(CheckError::UnorientedToken(cell_index));
+    }
+    let mut node = SolverNode::new(cells.clone(), vec![], 0);
+    node.mirror_rules = mirror_rules.clone();
+    let mut checker = Checker::from_solver_node(node).recording_beam_path();
+    if let Some(steps) = steps {
+        checker = checker.stopping_after(steps);
+    }
+    checker.check()
+}
+
+/// The name of a cell as on the card, e.g. "C3": rows are lettered A-E from the top, columns
+/// numbered 1-5 from the left
+pub fn cell_name(cell_index: usize) -> String {
+    let row = ["E", "D", "C", "B", "A"][cell_index / 5];
+    format!("{}{}", row, cell_index % 5 + 1)
+}
+
+/// The beam's journey through `cells` in words, one sentence per step, starting from the laser.
+/// `path` is the steps of beam_path(cells)
+pub fn explain(
+    cells: &[Option<Token>; 25],
+    path: &[(usize, Orientation)],
+    mirror_rules: &MirrorRules,
+) -> Vec<String> {
+    type R = token::LaserTokenInteractionResult;
+    let mut result = vec![];
+
+    if let Some((cell_index, Some(orientation))) = laser_state(cells) {
+        result.push(format!(
+            "The laser fires {:?} from {}.",
+            orientation,
+            cell_name(cell_index)
+        ));
+    }
+
+    for (cell_index, inbound) in path {
+        let mut token = match &cells[*cell_index] {
+            Some(token) => token.clone(),
+            None => continue,
+        };
+        let name = cell_name(*cell_index);
+        let sentence = match token.outbound_lasers_with_rules(inbound, mirror_rules) {
+            [R::OutboundLaser(reflected), R::OutboundLaser(passed)] => format!(
+                "The beam splits at the {} at {}: part reflects {:?}, part passes {:?}.",
+                token.type_(),
+                name,
+                reflected,
+                passed
+            ),
+            [R::OutboundLaser(outbound), _] if &outbound == inbound => format!(
+                "The beam passes {:?} through the {} at {}.",
+                outbound,
+                token.type_(),
+                name
+            ),
+            [R::OutboundLaser(outbound), _] => format!(
+                "The beam reflects {:?} off the {} at {}.",
+                outbound,
+                token.type_(),
+                name
+            ),
+            _ if token.target_lit() == Some(true) => {
+                format!(
+                    "The beam hits the target of the {} at {}.",
+                    token.type_(),
+                    name
+                )
+            }
+            [R::NoOutboundLaser { valid: true }, _] => {
+                format!("The beam returns into the {} at {}.", token.type_(), name)
+            }
+            _ => format!("The beam is blocked by the {} at {}.", token.type_(), name),
+        };
+        result.push(sentence);
+    }
+
+    if let Some((cell_index, orientation)) = off_board_exit(cells, mirror_rules) {
+        result.push(format!(
+            "The beam leaves the board heading {:?} from {}.",
+            orientation,
+            cell_name(cell_index)
+        ));
+    }
+
+    result
+}
+
+/// Where the beam leaves the board: the cell it leaves from, and the direction it's going.
+/// None if no beam leaves the board, or the laser is missing or unoriented
+pub fn off_board_exit(
+    cells: &[Option<Token>; 25],
+    mirror_rules: &MirrorRules,
+) -> Option<(usize, Orientation)> {
+    let mut node = SolverNode::new(cells.clone(), vec![], 0);
+    node.mirror_rules = mirror_rules.clone();
+    Checker::from_solver_node(node)
+        .check()
+        .ok()?
+        .off_board_exit()
+}
+
+/// The cells after tracing the beam, with each token's lit state (and each target mirror's target lit
+/// state) set. A token is lit only if the beam touched it, so a cell blocker off the beam's path
+/// isn't. Returns Err if there is no laser, or it's unoriented
+pub fn traced_cells(
+    cells: &[Option<Token>; 25],
+    mirror_rules: &MirrorRules,
+) -> Result<[Option<Token>; 25], CheckError> {
+    let mut node = SolverNode::new(cells.clone(), vec![], 0);
+    node.mirror_rules = mirror_rules.clone();
+    let checker = Checker::from_solver_node(node).check()?;
+    let mut result = checker.cells().clone();
+    for (cell, lit) in result.iter_mut().zip(checker.lit_map()) {
+        if let Some(token) = cell {
+            token.lit = lit;
+        }
+    }
+    Ok(result)
+}
+
+/// Whether the beam comes back to a wall-side of the laser, which no solution allows. False if the
+/// laser is missing or unoriented
+pub fn beam_hits_laser_side(cells: &[Option<Token>; 25], mirror_rules: &MirrorRules) -> bool {
+    let mut node = SolverNode::new(cells.clone(), vec![], 0);
+    node.mirror_rules = mirror_rules.clone();
+    Checker::from_solver_node(node)
+        .check()
+        .is_ok_and(|checker| checker.beam_hit_laser_side())
+}
+
+/// The board turned a quarter turn clockwise: each token moves to its new cell and is turned with
+/// the board, so the laser's path is turned too
+#[allow(dead_code)]
+pub fn rotate_board_cw(cells: &[Option<Token>; 25]) -> [Option<Token>; 25] {
+    let mut result: [Option<Token>; 25] = Default::default();
+    for (cell_index, cell) in cells.iter().enumerate() {
+        // cell 0 is bottom left; the bottom row becomes the left column
+        let (row, column) = (cell_index / 5, cell_index % 5);
+        let mut cell = cell.clone();
+        if let Some(token) = cell.as_mut() {
+            // a cell blocker is always North
+            if token.type_() != &TokenType::CellBlocker {
+                token.orientation = token.orientation.as_ref().map(Orientation::rotate_cw);
+            }
+        }
+        result[(4 - column) * 5 + row] = cell;
+    }
+    result
+}
+
+/// The board mirrored left to right, with each token turned so the laser's path is mirrored too.
+/// Mirrors are chiral: a mirrored target mirror can keep either its mirror or its target facing the
+/// mirrored beams, not both. So the board is traced first; a target mirror whose target is lit keeps
+/// its target, and any other keeps its mirror. Returns None if a lit target mirror also reflects a beam
+#[allow(dead_code)]
+pub fn flip_board_horizontal(cells: &[Option<Token>; 25]) -> Option<[Option<Token>; 25]> {
+    // which target mirrors have their target lit, and which cells reflect a beam onward
+    let mut target_lit = [false; 25];
+    let mut reflects = [false; 25];
+    if let Ok(checker) =
+        Checker::from_solver_node(SolverNode::new(cells.clone(), vec![], 0)).check()
+    {
+        for (cell_index, cell) in checker.cells().iter().enumerate() {
+            target_lit[cell_index] = cell
+                .as_ref()
+                .is_some_and(|token| token.target_lit() == Some(true));
+        }
+        for cell_index in checker.cells_with_active_laser() {
+            reflects[cell_index] = true;
+        }
+    }
+
+    let mut result: [Option<Token>; 25] = Default::default();
+    for (cell_index, cell) in cells.iter().enumerate() {
+        let (row, column) = (cell_index / 5, cell_index % 5);
+        let mut cell = cell.clone();
+        if let Some(token) = cell.as_mut() {
+            if let Some(orientation) = token.orientation.as_ref() {
+                // a mirror facing reference orientation sides {S, W} after the flip faces {S, E}
+                let mirrored_mirror = Orientation::from_index((7 - orientation.to_index()) % 4);
+                token.orientation = Some(match token.type_() {
+                    TokenType::TargetMirror if target_lit[cell_index] => {
+                        if reflects[cell_index] {
+                            return None;
+                        }
+                        orientation.reflect_horizontal()
+                    }
+                    TokenType::TargetMirror | TokenType::BeamSplitter | TokenType::DoubleMirror => {
+                        mirrored_mirror
+                    }
+                    TokenType::Laser | TokenType::Checkpoint => orientation.reflect_horizontal(),
+                    TokenType::CellBlocker => orientation.clone(),
+                });
+            }
+        }
+        result[row * 5 + 4 - column] = cell;
+    }
+    Some(result)
+}
+
+/// The same board for every rotation and reflection of it, so duplicate puzzles can be found by
+/// comparing canonical forms: the smallest of the 8 rotated and flipped boards, comparing cell by cell
+/// from cell 0 by token type, then orientation, then whether it must be lit (empty cells first).
+/// Symmetric tokens take their canonical orientation. A reflection flip_board_horizontal can't make
+/// is left out
+#[allow(dead_code)]
+pub fn canonical_form(cells: &[Option<Token>; 25]) -> [Option<Token>; 25] {
+    // the sort key for a board
+    fn key(cells: &[Option<Token>; 25]) -> Vec<Option<(TokenType, Option<usize>, bool)>> {
+        cells
+            .iter()
+            .map(|cell| {
+                cell.as_ref().map(|token| {
+                    (
+                        *token.type_(),
+                        token.orientation().map(Orientation::to_index),
+                        token.must_light(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    let mut transforms = vec![];
+    for board in [Some(cells.clone()), flip_board_horizontal(cells)]
+        .into_iter()
+        .flatten()
+    {
+        let mut board = board;
+        for _ in 0..4 {
+            board = rotate_board_cw(&board);
+            let mut canonical = board.clone();
+            for token in canonical.iter_mut().flatten() {
+                token.canonicalize_orientation();
+            }
+            transforms.push(canonical);
+        }
+    }
+
+    transforms
+        .into_iter()
+        .min_by_key(key)
+        .expect("there is always the board itself")
+}
+
+/// The cells a beam reaches from the laser, including the cells where it stops, regardless of whether
+/// the targets are satisfied. Beams stop at unoriented tokens. If there is no oriented laser on the
+/// board, no cell is reached
+#[allow(dead_code)]
+pub fn reachable_cells(cells: &[Option<Token>; 25]) -> [bool; 25] {
+    Checker::from_solver_node(SolverNode::new(cells.clone(), vec![], 0))
+        .check()
+        .map_or([false; 25], |checker| checker.cells_reached())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solver::orientation::Orientation;
+    use crate::solver::puzzle_def::{solution_matches_puzzle, PuzzleDef};
+    use rand::SeedableRng;
+    use std::time;
+
+    // /| -- /  -- X
+    //       ||
+    //       []
+    // /| -- /
+    //       \\ -- |/
+    fn all_tokens_cells() -> [Option<Token>; 25] {
+        let mut cells: [Option<Token>; 25] = Default::default();
+
+        // laser in top right
+        cells[24] = Some(Token::laser(Some(Orientation::West)));
+
+        // splitting mirror piece on center col, top row cell
+        cells[22] = Some(Token::splitter(Some(Orientation::East)));
+
+        // target 1: top left cell, target facing east
+        cells[20] = Some(Token::target(Some(Orientation::East), false));
+
+        // gate piece, middle col  row[3]
+        cells[17] = Some(Token::checkpoint(Some(Orientation::South)));
+
+        // block piece, true center
+        cells[12] = Some(Token::blocker());
+
+        // splitting mirror piece on center col, row[1] cell
+        cells[7] = Some(Token::splitter(Some(Orientation::East)));
+
+        // double mirror piece on bottom middle cell, facing south
+        cells[2] = Some(Token::double(Some(Orientation::South)));
+
+        // target 2: left col, row[1] cell, facing east
+        cells[5] = Some(Token::target(Some(Orientation::East), false));
+
+        // target 3: bottom right cell, facing west
+        cells[4] = Some(Token::target(Some(Orientation::West), false));
+
+        cells
+    }
+
+    // the tokens on a board, with the cells they're in
+    fn placed_tokens(cells: &[Option<Token>; 25]) -> Vec<(usize, Token)> {
+        cells
+            .iter()
+            .enumerate()
+            .filter_map(|(cell_index, token)| token.clone().map(|token| (cell_index, token)))
+            .collect()
+    }
+
+    // asserts `solution` keeps the puzzle's fixed tokens, places its tokens to be added, and lights
+    // its targets. any such board passes, so the tests don't depend on the order of the search
+    fn assert_solves(
+        cells: &[Option<Token>; 25],
+        tokens_to_be_added: &[Token],
+        targets: u8,
+        solution: &[Option<Token>; 25],
+    ) {
+        let puzzle = PuzzleDef {
+            id: 0,
+            grid: cells.clone(),
+            to_be_added: tokens_to_be_added.to_vec(),
+            targets,
+        };
+        assert!(solution_matches_puzzle(&puzzle, solution));
+        assert_eq!(
+            check_board(solution.clone(), targets, &[], &[], &MirrorRules::default()),
+            Ok(true)
+        );
+    }
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    // fails to compile if an Rc or another type which can't cross threads gets into the solver
+    #[test]
+    fn test_send_sync() {
+        assert_send::<Orientation>();
+        assert_send::<Token>();
+        assert_send::<SolverNode>();
+        assert_send::<LaserMazeSolver>();
+        assert_send::<SharedSolver>();
+        assert_sync::<SharedSolver>();
+    }
+
+    #[test]
+    fn test_shared_solver() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[20] = Some(Token::target(None, false));
+        let solver = SharedSolver::new(LaserMazeSolver::new(cells, vec![], 1));
+        let solutions: Vec<_> = (0..2)
+            .map(|_| {
+                let solver = solver.clone();
+                std::thread::spawn(move || solver.solve())
+            })
+            .map(|thread| thread.join().unwrap())
+            .collect();
+        // the threads share one stack, so whichever solves first finds the solution
+        assert!(solutions
+            .iter()
+            .any(|solution| matches!(solution, Ok(Some(_)))));
+        assert!(solver.lock().solution_with_lit_state().is_some());
+    }
+
+    #[test]
+    fn test_checker_all_tokens() {
+        let mut solver = LaserMazeSolver::new(all_tokens_cells(), vec![], 3);
+        let result = solver
+            .stack
+            .pop()
+            .expect("LaserMazeSolver initializes with a node")
+            .check()
+            .unwrap()
+            .solved();
+        assert!(result)
+    }
+
+    #[test]
+    fn test_checker_rejects_extra_lit_target() {
+        // the same board lights 3 targets; when the challenge only asks for 2, lighting
+        // a 3rd target overshoots and the board must not count as solved
+        let mut solver = LaserMazeSolver::new(all_tokens_cells(), vec![], 2);
+        let result = solver
+            .stack
+            .pop()
+            .expect("LaserMazeSolver initializes with a node")
+            .check()
+            .unwrap()
+            .solved();
+        assert!(!result)
+    }
+
+    #[test]
+    fn test_reachable_cells() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        assert_eq!(reachable_cells(&cells), [false; 25]);
+
+        // laser in the bottom left pointing north, reflected east by a double mirror at cell 10
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[10] = Some(Token::double(Some(Orientation::East)));
+        let reachable = reachable_cells(&cells);
+        let expected = [0, 5, 10, 11, 12, 13, 14];
+        for (cell_index, reached) in reachable.iter().enumerate() {
+            assert_eq!(*reached, expected.contains(&cell_index), "{}", cell_index);
+        }
+
+        // the beam stops at an unoriented token
+        cells[10] = Some(Token::double(None));
+        let reachable = reachable_cells(&cells);
+        assert!(reachable[10]);
+        assert!(!reachable[11]);
+        assert!(!reachable[15]);
+
+        // the beam ends at a lit target
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+        let reachable = reachable_cells(&cells);
+        assert!(reachable[10]);
+        assert!(!reachable[15]);
+
+        // and at the back of a target, which rejects it
+        cells[10] = Some(Token::target(Some(Orientation::North), false));
+        let reachable = reachable_cells(&cells);
+        assert!(reachable[10]);
+        assert!(!reachable[15]);
+    }
+
+    #[test]
+    fn test_rotate_board_cw() {
+        let cells = all_tokens_cells();
+        let mut rotated = cells.clone();
+        for _ in 0..4 {
+            rotated = rotate_board_cw(&rotated);
+            // the laser's path turns with the board
+            assert_eq!(
+                check_board(rotated.clone(), 3, &[], &[], &MirrorRules::default()),
+                Ok(true)
+            );
+        }
+        assert_eq!(rotated, cells);
+
+        // the top left corner moves to the top right
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[20] = Some(Token::laser(Some(Orientation::East)));
+        assert_eq!(
+            rotate_board_cw(&cells)[24],
+            Some(Token::laser(Some(Orientation::South)))
+        );
+    }
+
+    #[test]
+    fn test_flip_board_horizontal() {
+        let cells = all_tokens_cells();
+        let flipped = flip_board_horizontal(&cells).unwrap();
+        // the laser's path is mirrored, so the board is still solved
+        assert_eq!(
+            check_board(flipped.clone(), 3, &[], &[], &MirrorRules::default()),
+            Ok(true)
+        );
+        assert_eq!(flipped[20], Some(Token::laser(Some(Orientation::East))));
+        assert_eq!(flip_board_horizontal(&flipped), Some(cells));
+
+        // a double mirror turning the laser west turns it east after the flip
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::laser(Some(Orientation::North)));
+        cells[12] = Some(Token::double(Some(Orientation::North)));
+        let flipped = flip_board_horizontal(&cells).unwrap();
+        // (West is the same as East for a double mirror)
+        assert_eq!(flipped[12], Some(Token::double(Some(Orientation::West))));
+        let reachable = reachable_cells(&cells);
+        let flipped_reachable = reachable_cells(&flipped);
+        for cell_index in 0..25 {
+            assert_eq!(
+                flipped_reachable[cell_index],
+                reachable[(cell_index / 5) * 5 + 4 - cell_index % 5]
+            );
+        }
+    }
+
+    #[test]
+    fn test_laser_state() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[3] = Some(Token::target(Some(Orientation::North), false));
+        assert_eq!(laser_state(&cells), None);
+
+        cells[7] = Some(Token::laser(None));
+        assert_eq!(laser_state(&cells), Some((7, None)));
+
+        cells[7] = Some(Token::laser(Some(Orientation::West)));
+        assert_eq!(laser_state(&cells), Some((7, Some(Orientation::West))));
+    }
+
+    #[test]
+    fn test_is_fully_oriented() {
+        assert!(is_fully_oriented(&Default::default()));
+        assert!(is_fully_oriented(&all_tokens_cells()));
+
+        let mut cells = all_tokens_cells();
+        let cell_index = cells.iter().position(|cell| cell.is_some()).unwrap();
+        cells[cell_index].as_mut().unwrap().orientation = None;
+        assert!(!is_fully_oriented(&cells));
+    }
+
+    #[test]
+    fn test_token_counts() {
+        let counts = token_counts(&all_tokens_cells(), &[Token::splitter(None)]);
+        assert_eq!(counts.len(), TOKEN_TYPES.len());
+        assert_eq!(counts[&TokenType::Laser], 1);
+        assert_eq!(counts[&TokenType::TargetMirror], 3);
+        assert_eq!(counts[&TokenType::BeamSplitter], 3);
+
+        let counts = token_counts(&Default::default(), &[]);
+        assert!(counts.values().all(|count| *count == 0));
+    }
+
+    #[test]
+    fn test_traced_cells() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::laser(Some(Orientation::North)));
+        cells[12] = Some(Token::target(Some(Orientation::South), false));
+        cells[14] = Some(Token::target(Some(Orientation::South), false));
+        let traced = traced_cells(&cells, &MirrorRules::default()).unwrap();
+        let lit_state = |cell_index: usize| {
+            let token = traced[cell_index].as_ref().unwrap();
+            (token.lit, token.target_lit())
+        };
+        assert_eq!(lit_state(12), (true, Some(true)));
+        assert_eq!(lit_state(14), (false, Some(false)));
+
+        // a cell blocker the beam doesn't reach isn't lit
+        cells[4] = Some(Token::blocker());
+        let traced = traced_cells(&cells, &MirrorRules::default()).unwrap();
+        assert!(!traced[4].as_ref().unwrap().lit);
+
+        cells[2] = None;
+        assert_eq!(
+            traced_cells(&cells, &MirrorRules::default()),
+            Err(CheckError::LaserMissing)
+        );
+    }
+
+    #[test]
+    fn test_targets_bounds() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::laser(Some(Orientation::North)));
+        cells[12] = Some(Token::target(None, true));
+        cells[22] = Some(Token::target(None, false));
+        let to_be_added = [Token::target(None, true), Token::splitter(None)];
+        assert_eq!(targets_bounds(&cells, &to_be_added), (2, 3));
+        assert_eq!(suggested_targets(&cells, &to_be_added), 2);
+
+        // no target mirror must be lit
+        cells[12] = None;
+        assert_eq!(targets_bounds(&cells, &[]), (1, 1));
+        assert_eq!(suggested_targets(&cells, &[]), 1);
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let cells = all_tokens_cells();
+        let canonical = canonical_form(&cells);
+        assert_eq!(canonical_form(&canonical), canonical);
+        assert_eq!(canonical_form(&rotate_board_cw(&cells)), canonical);
+        assert_eq!(
+            canonical_form(&rotate_board_cw(&flip_board_horizontal(&cells).unwrap())),
+            canonical
+        );
+
+        // a lone laser in a corner, firing along an edge: the same board from any corner
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[24] = Some(Token::laser(Some(Orientation::South)));
+        let mut reflected: [Option<Token>; 25] = Default::default();
+        reflected[24] = Some(Token::laser(Some(Orientation::West)));
+        let mut rotated: [Option<Token>; 25] = Default::default();
+        rotated[0] = Some(Token::laser(Some(Orientation::North)));
+        assert_eq!(canonical_form(&cells), canonical_form(&reflected));
+        assert_eq!(canonical_form(&cells), canonical_form(&rotated));
+        // but not firing off the board
+        let mut other: [Option<Token>; 25] = Default::default();
+        other[24] = Some(Token::laser(Some(Orientation::North)));
+        assert_ne!(canonical_form(&cells), canonical_form(&other));
+
+        // symmetric tokens compare equal whichever of their equivalent orientations they're in
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[12] = Some(Token::double(Some(Orientation::South)));
+        let mut equivalent: [Option<Token>; 25] = Default::default();
+        equivalent[12] = Some(Token::double(Some(Orientation::North)));
+        assert_eq!(canonical_form(&cells), canonical_form(&equivalent));
+    }
+
+    #[test]
+    fn test_rotated_puzzle_solvable() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[6] = Some(Token::target(Some(Orientation::West), true));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+        let tokens_to_be_added = vec![Token::splitter(None)];
+
+        let mut solver = LaserMazeSolver::new(rotate_board_cw(&cells), tokens_to_be_added, 2);
+        assert!(solver.solve().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_check_board() {
+        assert_eq!(
+            check_board(all_tokens_cells(), 3, &[], &[], &MirrorRules::default()),
+            Ok(true)
+        );
+        assert_eq!(
+            check_board(all_tokens_cells(), 2, &[], &[], &MirrorRules::default()),
+            Ok(false)
+        );
+
+        // unoriented tokens can't be checked
+        let mut cells = all_tokens_cells();
+        cells[7] = Some(Token::splitter(None));
+        assert_eq!(
+            check_board(cells, 3, &[], &[], &MirrorRules::default()),
+            Err(CheckError::UnorientedToken(7))
+        );
+
+        let mut cells = all_tokens_cells();
+        cells[24] = None;
+        assert_eq!(
+            check_board(cells, 3, &[], &[], &MirrorRules::default()),
+            Err(CheckError::LaserMissing)
+        );
+
+        // the targets are lit, but a token is still to be added
+        assert_eq!(
+            check_board_with_unplaced(
+                all_tokens_cells(),
+                &[Token::target(None, false)],
+                3,
+                &[],
+                &[],
+                &MirrorRules::default()
+            ),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_unplaced_tokens() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[6] = Some(Token::target(None, true));
+
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+        ];
+        let solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added, 2);
+        assert_eq!(solver.unplaced_tokens(&cells).len(), 3);
+
+        // place one of the target mirrors and the beam splitter; moving a token that started on
+        // the grid doesn't count as placing a token
+        let mut current_grid = cells;
+        current_grid[7] = current_grid[6].take();
+        current_grid[10] = Some(Token::target(Some(Orientation::South), false));
+        current_grid[5] = Some(Token::splitter(Some(Orientation::East)));
+        assert_eq!(
+            solver.unplaced_tokens(&current_grid),
+            vec![Token::target(None, false)]
+        );
+    }
+
+    #[test]
+    fn test_solver_simple() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[6] = Some(Token::target(Some(Orientation::West), true));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+
+        let tokens_to_be_added = vec![Token::splitter(None)];
+
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 2);
+
+        let result = solver.solve();
+
+        assert!(result.is_ok());
+        let solution = result.unwrap().unwrap();
+
+        // the splitter lights both targets from the cell above the laser, the only cell it can
+        assert_eq!(solution[5], Some(Token::splitter(Some(Orientation::East))));
+        assert_solves(&cells, &tokens_to_be_added, 2, &solution);
+    }
+
+    #[test]
+    fn test_solve_trace() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[6] = Some(Token::target(Some(Orientation::West), true));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+        let tokens_to_be_added = vec![Token::splitter(None)];
+
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 2);
+        let solution = solver.solve().unwrap().unwrap();
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 2);
+        let tree = solver.solve_trace().unwrap();
+
+        // the first node is the puzzle, and every other node branched from a node searched before it
+        assert_eq!(tree.nodes[0].parent, None);
+        assert_eq!(tree.nodes[0].cells, cells);
+        assert_eq!(tree.nodes[0].tokens_to_be_added, tokens_to_be_added);
+        for (i, node) in tree.nodes.iter().enumerate().skip(1) {
+            assert!(node.parent.is_some_and(|parent| parent < i));
+        }
+
+        // the search ends at the solution .solve() finds, on the last node
+        let last = tree.nodes.last().unwrap();
+        assert!(last.solution);
+        assert!(last.tokens_to_be_added.is_empty());
+        assert_eq!(tree.nodes.iter().filter(|node| node.solution).count(), 1);
+        let mut traced_solution = last.cells.clone();
+        traced_solution
+            .iter_mut()
+            .flatten()
+            .for_each(|token| token.reset());
+        assert_eq!(traced_solution, solution);
+
+        // an invalid puzzle still can't be solved
+        let mut solver = LaserMazeSolver::new(Default::default(), vec![], 4);
+        assert!(solver.solve_trace().is_err());
+    }
+
+    #[test]
+    fn test_solver_max_nodes() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[6] = Some(Token::target(Some(Orientation::West), true));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+
+        let tokens_to_be_added = vec![Token::splitter(None)];
+
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 2);
+        solver.max_nodes = Some(1);
+        assert_eq!(
+            solver.solve(),
+            Err(String::from(
+                "Gave up after searching 1 nodes without a solution!"
+            ))
+        );
+
+        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 2);
+        solver.max_nodes = Some(1000);
+        assert!(solver.solve().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_solver_mirror_rules() {
+        // the laser points north at a double mirror; only a transparent double mirror reaches the target
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::new(
+            TokenType::Laser,
+            Some(Orientation::North),
+            false,
+        ));
+        cells[12] = Some(Token::new(
+            TokenType::DoubleMirror,
+            Some(Orientation::North),
+            false,
+        ));
+        cells[22] = Some(Token::new(
+            TokenType::TargetMirror,
+            Some(Orientation::South),
+            true,
+        ));
+        assert_eq!(
+            check_board(cells.clone(), 1, &[], &[], &MirrorRules::default()),
+            Ok(false)
+        );
+        assert_eq!(
+            check_board(
+                cells.clone(),
+                1,
+                &[],
+                &[],
+                &MirrorRules::transparent_double_mirror()
+            ),
+            Ok(true)
+        );
+        let mut solver = LaserMazeSolver::new(cells, vec![], 1)
+            .with_mirror_rules(MirrorRules::transparent_double_mirror());
+        assert!(matches!(solver.solve(), Ok(Some(_))));
+    }
+
+    #[test]
+    fn test_redundant_tokens() {
+        // the laser already shines straight into the target, so the double mirror isn't needed
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::laser(Some(Orientation::North)));
+        cells[22] = Some(Token::target(Some(Orientation::South), false));
+        let mut solver = LaserMazeSolver::new(cells.clone(), vec![Token::double(None)], 1);
+        assert_eq!(solver.redundant_tokens(), Ok(vec![TokenType::DoubleMirror]));
+
+        // the only target is needed
+        cells[22] = None;
+        let mut solver = LaserMazeSolver::new(cells, vec![Token::target(None, false)], 1);
+        assert_eq!(solver.redundant_tokens(), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_recheck() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[6] = Some(Token::target(Some(Orientation::West), true));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+        let mut solver = LaserMazeSolver::new(cells, vec![Token::splitter(None)], 2);
+        let solved = solver.solve().unwrap().unwrap();
+
+        // turning the splitter only needs a check
+        let mut turned = solved.clone();
+        turned[5] = Some(Token::splitter(Some(Orientation::North)));
+        assert_eq!(
+            solver.recheck(&solved, &turned, 5),
+            Ok(Recheck::Checked(false))
+        );
+        assert_eq!(
+            solver.recheck(&solved, &solved, 5),
+            Ok(Recheck::Checked(true))
+        );
+
+        // removing it searches again
+        let mut removed = solved.clone();
+        removed[5] = None;
+        assert_eq!(
+            solver.recheck(&solved, &removed, 5),
+            Ok(Recheck::Searched(Some(solved.clone())))
+        );
+
+        // only one cell may change
+        assert!(solver.recheck(&solved, &removed, 6).is_err());
+        // an unoriented token can't be checked
+        turned[5] = Some(Token::splitter(None));
+        assert!(solver.recheck(&solved, &turned, 5).is_err());
+    }
+
+    #[test]
+    fn test_solve_minimal_pieces() {
+        // the laser already shines straight into the target, so the double mirror can be left out
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::laser(Some(Orientation::North)));
+        cells[22] = Some(Token::target(Some(Orientation::South), false));
+        let mut solver = LaserMazeSolver::new(cells.clone(), vec![Token::double(None)], 1);
+        assert_eq!(solver.solve_minimal_pieces(), Ok(Some(cells.clone())));
+        // .solve() has to place the double mirror, and there's nowhere in the beam to put it
+        let mut solver = LaserMazeSolver::new(cells.clone(), vec![Token::double(None)], 1);
+        assert_eq!(solver.solve(), Ok(None));
+
+        // one of the two targets to be added is needed
+        cells[22] = None;
+        let mut solver = LaserMazeSolver::new(
+            cells,
+            vec![Token::target(None, false), Token::target(None, false)],
+            1,
+        );
+        let solution = solver.solve_minimal_pieces().unwrap().unwrap();
+        assert_eq!(placed_tokens(&solution).len(), 2);
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::laser(Some(Orientation::North)));
+        cells[12] = Some(Token::double(Some(Orientation::East)));
+        cells[14] = Some(Token::target(Some(Orientation::West), false));
+        let path = beam_path(&cells, &MirrorRules::default()).unwrap().steps;
+        assert_eq!(
+            path,
+            vec![(12, Orientation::North), (14, Orientation::East)]
+        );
+        assert_eq!(
+            explain(&cells, &path, &MirrorRules::default()),
+            vec![
+                "The laser fires North from E3.",
+                "The beam reflects East off the Double Mirror at C3.",
+                "The beam hits the target of the Target Mirror at C5.",
+            ]
+        );
+
+        // facing south, the target's back is to the beam, and the split beam leaves the board
+        cells[14] = Some(Token::target(Some(Orientation::South), false));
+        cells[12] = Some(Token::splitter(Some(Orientation::East)));
+        let path = beam_path(&cells, &MirrorRules::default()).unwrap().steps;
+        assert_eq!(
+            explain(&cells, &path, &MirrorRules::default())[1..],
+            [
+                "The beam splits at the Beam Splitter at C3: part reflects East, part passes North.",
+                "The beam is blocked by the Target Mirror at C5.",
+                "The beam leaves the board heading North from A3.",
+            ]
+        );
+
+        // under the house rule, the double mirror lets the beam straight through
+        cells[12] = Some(Token::double(Some(Orientation::East)));
+        cells[14] = None;
+        let rules = MirrorRules::transparent_double_mirror();
+        let path = beam_path(&cells, &rules).unwrap().steps;
+        assert_eq!(path, vec![(12, Orientation::North)]);
+        assert_eq!(
+            explain(&cells, &path, &rules)[1..],
+            [
+                "The beam passes North through the Double Mirror at C3.",
+                "The beam leaves the board heading North from A3.",
+            ]
+        );
+
+        cells[12] = Some(Token::splitter(None));
+        assert_eq!(
+            beam_path(&cells, &MirrorRules::default()),
+            Err(CheckError::UnorientedToken(12))
+        );
+    }
+
+    #[test]
+    fn test_beam_path_until_failure() {
+        // the splitter sends the beam north and east. the north beam hits the back of the target
+        // mirror on the second step, while the east beam carries on across the board
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[5] = Some(Token::splitter(Some(Orientation::East)));
+        cells[10] = Some(Token::target(Some(Orientation::East), false));
+        let path = beam_path_until_failure(&cells, &MirrorRules::default()).unwrap();
+        assert_eq!(
+            path.steps,
+            vec![(5, Orientation::North), (10, Orientation::North)]
+        );
+        assert_eq!(path.directions_at(6), vec![Orientation::East]);
+        assert!(path.directions_at(7).is_empty());
+        assert_eq!(
+            beam_path(&cells, &MirrorRules::default())
+                .unwrap()
+                .directions_at(9),
+            vec![Orientation::East]
+        );
+
+        // the beam never goes wrong on a solved board, so it's shown all the way
+        assert_eq!(
+            beam_path_until_failure(&all_tokens_cells(), &MirrorRules::default()),
+            beam_path(&all_tokens_cells(), &MirrorRules::default())
+        );
+
+        cells[5] = Some(Token::splitter(None));
+        assert_eq!(
+            beam_path_until_failure(&cells, &MirrorRules::default()),
+            Err(CheckError::UnorientedToken(5))
+        );
+    }
+
+    #[test]
+    fn test_beam_path_directions() {
+        // the laser fires north into a splitter at D3, which passes the beam north and reflects it
+        // east; double mirrors at D4 and B4 bring the east beam back west across B3
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::laser(Some(Orientation::North)));
+        cells[7] = Some(Token::splitter(Some(Orientation::East)));
+        cells[8] = Some(Token::double(Some(Orientation::East)));
+        cells[18] = Some(Token::double(Some(Orientation::North)));
+        let path = beam_path(&cells, &MirrorRules::default()).unwrap();
+        assert_eq!(path.directions_at(2), vec![Orientation::North]);
+        assert_eq!(
+            path.directions_at(7),
+            vec![Orientation::North, Orientation::East]
+        );
+        assert_eq!(path.directions_at(13), vec![Orientation::North]);
+        assert_eq!(
+            path.directions_at(17),
+            vec![Orientation::North, Orientation::West]
+        );
+        assert_eq!(path.directions_at(0), vec![]);
+        // the beams cross in the empty cell B3, and at the splitter
+        assert!(path.beams_intersect_at(17));
+        assert!(path.beams_intersect_at(7));
+        assert!(!path.beams_intersect_at(12));
+    }
+
+    #[test]
+    fn test_solve_detailed() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::laser(None));
+        cells[22] = Some(Token::target(Some(Orientation::South), false));
+        let mut solver = LaserMazeSolver::new(cells.clone(), vec![], 1);
+        let checker = solver.solve_detailed().unwrap().unwrap();
+        assert!(checker.solved());
+        assert_eq!(checker.beam_path(), [(22, Orientation::North)]);
+        assert_eq!(
+            checker.cells()[22].as_ref().unwrap().target_lit(),
+            Some(true)
+        );
+        let mut solver = LaserMazeSolver::new(cells.clone(), vec![], 1);
+        assert_eq!(
+            checker.cells()[2].as_ref().unwrap().orientation(),
+            solver.solve().unwrap().unwrap()[2]
+                .as_ref()
+                .unwrap()
+                .orientation()
+        );
+
+        // no solution: the target faces away
+        cells[22] = Some(Token::target(Some(Orientation::North), true));
+        let mut solver = LaserMazeSolver::new(cells, vec![], 1);
+        assert!(solver.solve_detailed().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_solving_orientations() {
+        // only the laser facing north reaches the target
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[2] = Some(Token::laser(None));
+        cells[22] = Some(Token::target(Some(Orientation::South), false));
+        let mut solver = LaserMazeSolver::new(cells.clone(), vec![], 1);
+        assert_eq!(solver.solving_orientations(2), Ok(vec![Orientation::North]));
+
+        // the target needs an orientation too, and its own must light it
+        cells[2] = Some(Token::laser(Some(Orientation::North)));
+        cells[22] = Some(Token::target(None, false));
+        let mut solver = LaserMazeSolver::new(cells, vec![], 1);
+        assert_eq!(
+            solver.solving_orientations(22),
+            Ok(vec![Orientation::South])
+        );
+        assert!(solver.solving_orientations(2).is_err());
+        assert!(solver.solving_orientations(7).is_err());
+    }
+
+    #[test]
+    fn test_solver_waypoints() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        let tokens_to_be_added = vec![Token::target(None, false)];
+
+        // the only way through cell 15 is to place the target at the top of the column
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 1)
+            .with_waypoints(vec![15]);
+        let solution = solver.solve().unwrap().unwrap();
+        assert_eq!(
+            solution[20],
+            Some(Token::target(Some(Orientation::South), false))
+        );
+        assert_eq!(
+            check_board(solution.clone(), 1, &[15], &[], &MirrorRules::default()),
+            Ok(true)
+        );
+        assert_eq!(
+            check_board(solution, 1, &[16], &[], &MirrorRules::default()),
+            Ok(false)
+        );
+
+        let solver = LaserMazeSolver::new(cells, tokens_to_be_added, 1).with_waypoints(vec![25]);
+        assert_eq!(
+            solver.validate(),
+            Err(String::from("Invalid waypoint cell index!"))
+        );
     }
 
-    #[allow(dead_code)]
-    pub fn solve(&mut self) -> Result<Option<[Option<Token>; 25]>, String> {
-        // Returns Ok(Some(_)) if solution found, Ok(None) if no solution, Err(s) if
-        // invalid puzzle provided; s describes why the puzzle is invalid
-        self.validate()?;
+    #[test]
+    fn test_waypoint_on_lit_target() {
+        // the beam ends at the target, and that still passes through its cell
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+        assert_eq!(
+            check_board(cells.clone(), 1, &[10], &[], &MirrorRules::default()),
+            Ok(true)
+        );
+        assert_eq!(
+            check_board(cells, 1, &[15], &[], &MirrorRules::default()),
+            Ok(false)
+        );
+    }
 
-        while let Some(mut node) = self.stack.pop() {
-            match node.generate_branches() {
-                Ok(cells) => return Ok(Some(cells)),
-                Err(new_nodes) => self.stack.extend(new_nodes),
-            }
-        }
+    #[test]
+    fn test_solver_forbidden_cells() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        let tokens_to_be_added = vec![Token::target(None, false)];
+
+        // keeping the laser out of cell 10 leaves only the target in cell 5
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 1)
+            .with_forbidden_cells(vec![10]);
+        let solution = solver.solve().unwrap().unwrap();
+        assert_eq!(
+            solution[5],
+            Some(Token::target(Some(Orientation::South), false))
+        );
+        let mut through_cell_10 = cells.clone();
+        through_cell_10[20] = Some(Token::target(Some(Orientation::South), false));
+        assert_eq!(
+            check_board(
+                through_cell_10.clone(),
+                1,
+                &[],
+                &[],
+                &MirrorRules::default()
+            ),
+            Ok(true)
+        );
+        assert_eq!(
+            check_board(
+                through_cell_10.clone(),
+                1,
+                &[],
+                &[10],
+                &MirrorRules::default()
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            check_board_with_unplaced(through_cell_10, &[], 1, &[], &[10], &MirrorRules::default()),
+            Ok(false)
+        );
 
-        Ok(None)
+        let solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 1)
+            .with_forbidden_cells(vec![25]);
+        assert_eq!(
+            solver.validate(),
+            Err(String::from("Invalid forbidden cell index!"))
+        );
+        let solver = LaserMazeSolver::new(cells, tokens_to_be_added, 1)
+            .with_waypoints(vec![10])
+            .with_forbidden_cells(vec![10]);
+        assert_eq!(
+            solver.validate(),
+            Err(String::from("A waypoint can't also be a forbidden cell!"))
+        );
     }
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::solver::orientation::Orientation;
-    use std::time;
 
-    // /| -- /  -- X
-    //       ||
-    //       []
-    // /| -- /
-    //       \\ -- |/
     #[test]
-    fn test_checker_all_tokens() {
+    fn test_forbidden_cell_with_untouched_blocker() {
+        // the beam never reaches the cell blocker, so the cell it's in is never entered
         let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+        cells[4] = Some(Token::blocker());
+        assert_eq!(
+            check_board_with_unplaced(cells, &[], 1, &[], &[4], &MirrorRules::default()),
+            Ok(true)
+        );
+    }
 
-        // laser in top right
-        cells[24] = Some(Token::new(TokenType::Laser, Some(Orientation::West), false));
-
-        // splitting mirror piece on center col, top row cell
-        cells[22] = Some(Token::new(
-            TokenType::BeamSplitter,
-            Some(Orientation::East),
-            false,
-        ));
+    #[test]
+    fn test_solve_all_symmetric_double_mirror() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        // West is the same as East for a double mirror
+        cells[10] = Some(Token::double(Some(Orientation::West)));
+        let tokens_to_be_added = vec![Token::target(None, false)];
+
+        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 1);
+        let solutions = solver.solve_all().unwrap();
+        assert!(!solutions.is_empty());
+        for (i, solution) in solutions.iter().enumerate() {
+            assert_eq!(solution[10], Some(Token::double(Some(Orientation::East))));
+            assert!(!solutions[..i].contains(solution));
+        }
+    }
 
-        // target 1: top left cell, target facing east
-        cells[20] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::East),
-            false,
-        ));
+    #[test]
+    fn test_solve_channel() {
+        // the GUI enters the runtime in main.rs
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _enter = runtime.enter();
 
-        // gate piece, middle col  row[3]
-        cells[17] = Some(Token::new(
-            TokenType::Checkpoint,
-            Some(Orientation::South),
-            false,
-        ));
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[6] = Some(Token::target(Some(Orientation::West), true));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+        let tokens_to_be_added = vec![Token::splitter(None)];
+
+        let solver = LaserMazeSolver::new(cells, tokens_to_be_added, 2);
+        let mut events = solver.solve_channel();
+        let mut last_event = None;
+        while let Some(event) = events.blocking_recv() {
+            last_event = Some(event);
+        }
+        assert!(matches!(last_event, Some(SolveEvent::SolutionFound(_))));
 
-        // block piece, true center
-        cells[12] = Some(Token::new(
-            TokenType::CellBlocker,
-            Some(Orientation::West),
-            false,
-        ));
+        let solver = LaserMazeSolver::new(Default::default(), vec![], 4);
+        let mut events = solver.solve_channel();
+        assert!(matches!(events.blocking_recv(), Some(SolveEvent::Error(_))));
+        assert!(events.blocking_recv().is_none());
+    }
 
-        // splitting mirror piece on center col, row[1] cell
-        cells[7] = Some(Token::new(
-            TokenType::BeamSplitter,
-            Some(Orientation::East),
-            false,
-        ));
+    #[test]
+    fn test_try_new() {
+        assert_eq!(
+            LaserMazeSolver::try_new(Default::default(), vec![], 4).err(),
+            Some(String::from("Invalid number of targets!"))
+        );
 
-        // double mirror piece on bottom middle cell, facing south
-        cells[2] = Some(Token::new(
-            TokenType::DoubleMirror,
-            Some(Orientation::South),
-            false,
-        ));
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[10] = Some(Token::target(None, false));
+        assert!(LaserMazeSolver::try_new(cells.clone(), vec![], 1).is_ok());
+
+        // a second laser, whether on the grid or to be added, is rejected
+        assert_eq!(
+            LaserMazeSolver::try_new(cells.clone(), vec![Token::laser(None)], 1).err(),
+            Some(String::from("Invalid piece count for piece type Laser!"))
+        );
+        cells[4] = Some(Token::laser(None));
+        assert!(LaserMazeSolver::try_new(cells, vec![], 1).is_err());
+    }
 
-        // target 2: left col, row[1] cell, facing east
-        cells[5] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::East),
-            false,
-        ));
+    #[test]
+    fn test_solver_keeps_fixed_orientations() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+
+        // unoriented, the solver turns the target to face the laser
+        cells[10] = Some(Token::target(None, false));
+        let mut solver = LaserMazeSolver::new(cells.clone(), vec![], 1);
+        let solution = solver.solve().unwrap().unwrap();
+        assert_eq!(
+            solution[10],
+            Some(Token::target(Some(Orientation::South), false))
+        );
 
-        // target 3: bottom right cell, facing west
-        cells[4] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::West),
-            false,
-        ));
+        // a wrong orientation set by the user isn't overridden
+        cells[10] = Some(Token::target(Some(Orientation::North), false));
+        let mut solver = LaserMazeSolver::new(cells, vec![], 1);
+        assert_eq!(solver.solve(), Ok(None));
+    }
 
-        let mut solver = LaserMazeSolver::new(cells, vec![], 3);
-        let result = solver
-            .stack
-            .pop()
-            .expect("LaserMazeSolver initializes with a node")
-            .check()
-            .solved();
-        assert!(result)
+    #[test]
+    fn test_solver_seeded_rng() {
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[6] = Some(Token::target(Some(Orientation::West), true));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
+        let tokens_to_be_added = vec![Token::splitter(None)];
+
+        // the same seed searches in the same order, so finds the same solution
+        let solutions = (0..2)
+            .map(|_| {
+                let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 2);
+                solver.rng = Some(StdRng::seed_from_u64(62));
+                solver.solve().unwrap().unwrap()
+            })
+            .collect::<Vec<[Option<Token>; 25]>>();
+        assert_eq!(solutions[0], solutions[1]);
+        assert_eq!(
+            check_board(solutions[0].clone(), 2, &[], &[], &MirrorRules::default()),
+            Ok(true)
+        );
     }
 
     #[test]
-    fn test_solver_simple() {
+    fn test_solution_with_lit_state() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[0] = Some(Token::new(
-            TokenType::Laser,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[6] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::West),
-            true,
-        ));
-        cells[10] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::South),
-            false,
-        ));
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[6] = Some(Token::target(Some(Orientation::West), true));
+        cells[10] = Some(Token::target(Some(Orientation::South), false));
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
+        let tokens_to_be_added = vec![Token::splitter(None)];
 
         let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 2);
+        assert!(solver.solution_with_lit_state().is_none());
 
-        let t0 = time::Instant::now();
-        let result = solver.solve();
-        let t1 = time::Instant::now();
-
-        println!("{:?}", result.unwrap().unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        let solution = solver.solve().unwrap().unwrap();
+        assert!(solution
+            .iter()
+            .flatten()
+            .all(|token| !token.target_lit().unwrap_or(false)));
+
+        let lit_solution = solver
+            .solution_with_lit_state()
+            .expect("the solver found a solution");
+        assert!(lit_solution.iter().flatten().all(|token| token.lit));
+        assert_eq!(
+            lit_solution
+                .iter()
+                .flatten()
+                .filter(|token| token.target_lit() == Some(true))
+                .count(),
+            2
+        );
     }
 
     #[test]
     fn test_solver_puzzle_25() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[3] = Some(Token::new(TokenType::TargetMirror, None, true));
-        cells[7] = Some(Token::new(TokenType::Checkpoint, None, false));
-        cells[8] = Some(Token::new(TokenType::BeamSplitter, None, false));
-        cells[20] = Some(Token::new(TokenType::Laser, None, false));
-        cells[23] = Some(Token::new(
-            TokenType::CellBlocker,
-            Some(Orientation::East),
-            false,
-        ));
+        cells[3] = Some(Token::target(None, true));
+        cells[7] = Some(Token::checkpoint(None));
+        cells[8] = Some(Token::splitter(None));
+        cells[20] = Some(Token::laser(None));
+        cells[23] = Some(Token::blocker());
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, true));
-        tokens_to_be_added.push(Token::new(TokenType::DoubleMirror, None, false));
+        let tokens_to_be_added = vec![Token::target(None, true), Token::double(None)];
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 2);
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 2);
 
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
-        println!("{:?}", result.unwrap().unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        assert!(result.is_ok());
+        let solution = result.unwrap().unwrap();
+
+        assert_solves(&cells, &tokens_to_be_added, 2, &solution);
+    }
+
+    #[test]
+    fn test_solver_target_mirror_faces_out() {
+        // the target on the bottom edge must face south, out of the board, so its mirror sends the
+        // laser north into the other target
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[1] = Some(Token::target(None, false));
+        cells[3] = Some(Token::laser(Some(Orientation::West)));
+        cells[21] = Some(Token::target(Some(Orientation::South), false));
+
+        let mut solver = LaserMazeSolver::new(cells, vec![], 1);
+        let solution = solver.solve().unwrap().unwrap();
+        assert_eq!(
+            solution[1].as_ref().unwrap().orientation(),
+            Some(&Orientation::South)
+        );
+    }
+
+    #[test]
+    fn test_solver_pre_oriented_token_to_be_added() {
+        // the splitter has to go in cell 10, facing east, to light both targets
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        cells[14] = Some(Token::target(Some(Orientation::West), false));
+        cells[20] = Some(Token::target(Some(Orientation::South), false));
+
+        let mut solver = LaserMazeSolver::new(
+            cells.clone(),
+            vec![Token::splitter(Some(Orientation::East))],
+            2,
+        );
+        let solution = solver.solve().unwrap().unwrap();
+        assert_eq!(solution[10], Some(Token::splitter(Some(Orientation::East))));
+
+        // locked facing north, the splitter can't be turned to solve the puzzle
+        let mut solver =
+            LaserMazeSolver::new(cells, vec![Token::splitter(Some(Orientation::North))], 2);
+        assert_eq!(solver.solve(), Ok(None));
     }
 
     #[test]
     fn test_solver_puzzle_40() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[3] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::North),
-            true,
-        ));
-        cells[9] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::West),
-            true,
-        ));
-        cells[11] = Some(Token::new(
-            TokenType::DoubleMirror,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[17] = Some(Token::new(
-            TokenType::Checkpoint,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[20] = Some(Token::new(TokenType::Laser, None, false));
+        cells[3] = Some(Token::target(Some(Orientation::North), true));
+        cells[9] = Some(Token::target(Some(Orientation::West), true));
+        cells[11] = Some(Token::double(Some(Orientation::North)));
+        cells[17] = Some(Token::checkpoint(Some(Orientation::North)));
+        cells[20] = Some(Token::laser(None));
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+        ];
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 2);
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 2);
 
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
-        println!("{:?}", result.unwrap().unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        assert!(result.is_ok());
+        let solution = result.unwrap().unwrap();
+
+        assert_solves(&cells, &tokens_to_be_added, 2, &solution);
     }
 
     #[test]
     fn test_solver_puzzle_50() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[3] = Some(Token::new(
-            TokenType::CellBlocker,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[4] = Some(Token::new(TokenType::TargetMirror, None, true));
-        cells[6] = Some(Token::new(
-            TokenType::BeamSplitter,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[7] = Some(Token::new(TokenType::TargetMirror, None, true));
-        cells[13] = Some(Token::new(
-            TokenType::Checkpoint,
-            Some(Orientation::East),
-            false,
-        ));
-        cells[20] = Some(Token::new(TokenType::TargetMirror, None, true));
+        cells[3] = Some(Token::blocker());
+        cells[4] = Some(Token::target(None, true));
+        cells[6] = Some(Token::splitter(Some(Orientation::North)));
+        cells[7] = Some(Token::target(None, true));
+        cells[13] = Some(Token::checkpoint(Some(Orientation::East)));
+        cells[20] = Some(Token::target(None, true));
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::Laser, None, false));
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+            Token::laser(None),
+        ];
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 3);
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 3);
 
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
-        println!("{:?}", result.unwrap().unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        assert!(result.is_ok());
+        let solution = result.unwrap().unwrap();
+
+        assert_solves(&cells, &tokens_to_be_added, 3, &solution);
     }
 
     // 2nd to last puzzle with the laser's position not given
@@ -359,140 +2279,117 @@ mod test {
     fn test_solver_puzzle_54() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[3] = Some(Token::new(TokenType::TargetMirror, None, false));
-        cells[6] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::North),
-            true,
-        ));
-        cells[12] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::South),
-            true,
-        ));
-        cells[18] = Some(Token::new(TokenType::DoubleMirror, None, false));
-        cells[21] = Some(Token::new(TokenType::BeamSplitter, None, false));
-        cells[24] = Some(Token::new(TokenType::TargetMirror, None, false));
+        cells[3] = Some(Token::target(None, false));
+        cells[6] = Some(Token::target(Some(Orientation::North), true));
+        cells[12] = Some(Token::target(Some(Orientation::South), true));
+        cells[18] = Some(Token::double(None));
+        cells[21] = Some(Token::splitter(None));
+        cells[24] = Some(Token::target(None, false));
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::Laser, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
+        let tokens_to_be_added = vec![
+            Token::laser(None),
+            Token::target(None, false),
+            Token::splitter(None),
+        ];
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 3);
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 3);
 
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
-        println!("{:?}", result.unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        // the laser comes from the tray, and is placed exactly once
+        let solution = result.unwrap().unwrap();
+        assert_eq!(
+            solution
+                .iter()
+                .flatten()
+                .filter(|token| token.type_() == &TokenType::Laser)
+                .count(),
+            1
+        );
+
+        assert_solves(&cells, &tokens_to_be_added, 3, &solution);
     }
 
     #[test]
     fn test_solver_puzzle_55() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[2] = Some(Token::new(TokenType::TargetMirror, None, false));
-        cells[6] = Some(Token::new(TokenType::TargetMirror, None, false));
-        cells[9] = Some(Token::new(TokenType::TargetMirror, None, false));
-        cells[12] = Some(Token::new(TokenType::TargetMirror, None, false));
-        cells[18] = Some(Token::new(TokenType::TargetMirror, None, false));
-        cells[3] = Some(Token::new(TokenType::DoubleMirror, None, false));
-        cells[16] = Some(Token::new(TokenType::Laser, None, false));
+        cells[2] = Some(Token::target(None, false));
+        cells[6] = Some(Token::target(None, false));
+        cells[9] = Some(Token::target(None, false));
+        cells[12] = Some(Token::target(None, false));
+        cells[18] = Some(Token::target(None, false));
+        cells[3] = Some(Token::double(None));
+        cells[16] = Some(Token::laser(None));
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::Checkpoint, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
+        let tokens_to_be_added = vec![Token::checkpoint(None), Token::splitter(None)];
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 2);
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 2);
 
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
-        println!("{:?}", result.unwrap().unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        assert!(result.is_ok());
+        let solution = result.unwrap().unwrap();
+
+        assert_solves(&cells, &tokens_to_be_added, 2, &solution);
     }
 
     #[test]
     fn test_solver_puzzle_59() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[6] = Some(Token::new(
-            TokenType::Laser,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[8] = Some(Token::new(TokenType::Checkpoint, None, false));
-        cells[10] = Some(Token::new(TokenType::TargetMirror, None, true));
-        cells[12] = Some(Token::new(TokenType::DoubleMirror, None, false));
-        cells[15] = Some(Token::new(TokenType::TargetMirror, None, false));
-        cells[17] = Some(Token::new(
-            TokenType::CellBlocker,
-            Some(Orientation::East),
-            false,
-        ));
-        cells[18] = Some(Token::new(TokenType::BeamSplitter, None, false));
+        cells[6] = Some(Token::laser(Some(Orientation::North)));
+        cells[8] = Some(Token::checkpoint(None));
+        cells[10] = Some(Token::target(None, true));
+        cells[12] = Some(Token::double(None));
+        cells[15] = Some(Token::target(None, false));
+        cells[17] = Some(Token::blocker());
+        cells[18] = Some(Token::splitter(None));
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+        ];
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 3);
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 3);
 
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
-        println!("{:?}", result.unwrap().unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        assert!(result.is_ok());
+        let solution = result.unwrap().unwrap();
+
+        assert_solves(&cells, &tokens_to_be_added, 3, &solution);
     }
 
     #[test]
     fn test_solver_puzzle_60() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[9] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::North),
-            true,
-        ));
-        cells[23] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::West),
-            true,
-        ));
-        cells[15] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::South),
-            false,
-        ));
-        cells[1] = Some(Token::new(TokenType::DoubleMirror, None, false));
-        cells[12] = Some(Token::new(TokenType::Checkpoint, None, false));
-        cells[11] = Some(Token::new(
-            TokenType::CellBlocker,
-            Some(Orientation::South),
-            false,
-        ));
+        cells[9] = Some(Token::target(Some(Orientation::North), true));
+        cells[23] = Some(Token::target(Some(Orientation::West), true));
+        cells[15] = Some(Token::target(Some(Orientation::South), false));
+        cells[1] = Some(Token::double(None));
+        cells[12] = Some(Token::checkpoint(None));
+        cells[11] = Some(Token::blocker());
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::Laser, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
+        let tokens_to_be_added = vec![
+            Token::laser(None),
+            Token::splitter(None),
+            Token::splitter(None),
+            Token::target(None, false),
+            Token::target(None, false),
+        ];
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 3);
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 3);
 
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
-        println!("{:?}", result.unwrap().unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        assert!(result.is_ok());
+        let solution = result.unwrap().unwrap();
+
+        assert_solves(&cells, &tokens_to_be_added, 3, &solution);
     }
 
     #[test]
@@ -500,86 +2397,67 @@ mod test {
     fn test_solver_puzzle_153() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[9] = Some(Token::new(
-            TokenType::Checkpoint,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[11] = Some(Token::new(
-            TokenType::BeamSplitter,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[13] = Some(Token::new(
-            TokenType::DoubleMirror,
-            Some(Orientation::East),
-            false,
-        ));
-        cells[16] = Some(Token::new(
-            TokenType::TargetMirror,
-            Some(Orientation::West),
-            true,
-        ));
-        cells[18] = Some(Token::new(
-            TokenType::CellBlocker,
-            Some(Orientation::North),
-            false,
-        ));
+        cells[9] = Some(Token::checkpoint(Some(Orientation::North)));
+        cells[11] = Some(Token::splitter(Some(Orientation::North)));
+        cells[13] = Some(Token::double(Some(Orientation::East)));
+        cells[16] = Some(Token::target(Some(Orientation::West), true));
+        cells[18] = Some(Token::blocker());
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::Laser, None, false));
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+            Token::laser(None),
+        ];
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 3);
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 3);
 
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
-        println!("{:?}", result.unwrap().unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        assert!(result.is_ok());
+        let solution = result.unwrap().unwrap();
+
+        assert_solves(&cells, &tokens_to_be_added, 3, &solution);
     }
 
     #[test]
     fn test_solver_puzzle_62() {
         // Bonus Challenge 2
         let mut cells: [Option<Token>; 25] = Default::default();
-        cells[0] = Some(Token::new(TokenType::TargetMirror, None, false));
-        cells[11] = Some(Token::new(TokenType::Laser, None, false));
-        cells[14] = Some(Token::new(TokenType::DoubleMirror, None, false));
-        cells[17] = Some(Token::new(
-            TokenType::Checkpoint,
-            Some(Orientation::East),
-            false,
-        ));
-        cells[22] = Some(Token::new(TokenType::TargetMirror, None, false));
-
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
+        cells[0] = Some(Token::target(None, false));
+        cells[11] = Some(Token::laser(None));
+        cells[14] = Some(Token::double(None));
+        cells[17] = Some(Token::checkpoint(Some(Orientation::East)));
+        cells[22] = Some(Token::target(None, false));
+
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+            Token::splitter(None),
+        ];
+
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 2);
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 2);
-
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
         let solution = result.unwrap().unwrap();
-        println!("{:?}", solution);
-        println!("Processed in {:?}", t1 - t0);
 
-        let split_1 = solution[13].clone().unwrap();
-        let split_2 = solution[18].clone().unwrap();
-
-        assert_eq!(split_1.type_(), &TokenType::BeamSplitter);
-        assert_eq!(split_2.type_(), &TokenType::BeamSplitter);
+        // the board is "solved" before the last beam splitter is placed; make sure the solver
+        // didn't return that board, wherever it puts the beam splitters
+        assert_eq!(
+            solution
+                .iter()
+                .flatten()
+                .filter(|token| token.type_() == &TokenType::BeamSplitter)
+                .count(),
+            2
+        );
+        assert!(solver.unplaced_tokens(&solution).is_empty());
+        assert_solves(&cells, &tokens_to_be_added, 2, &solution);
     }
 
     // bonus 99
@@ -587,39 +2465,147 @@ mod test {
     fn test_solver_puzzle_159() {
         let mut cells: [Option<Token>; 25] = Default::default();
 
-        cells[10] = Some(Token::new(
-            TokenType::Checkpoint,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[16] = Some(Token::new(
-            TokenType::DoubleMirror,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[20] = Some(Token::new(
-            TokenType::CellBlocker,
-            Some(Orientation::North),
-            false,
-        ));
-        cells[23] = Some(Token::new(TokenType::Laser, None, false));
+        cells[10] = Some(Token::checkpoint(Some(Orientation::North)));
+        cells[16] = Some(Token::double(Some(Orientation::North)));
+        cells[20] = Some(Token::blocker());
+        cells[23] = Some(Token::laser(None));
 
-        let mut tokens_to_be_added = vec![];
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::TargetMirror, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
-        tokens_to_be_added.push(Token::new(TokenType::BeamSplitter, None, false));
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+            Token::splitter(None),
+        ];
 
-        let mut solver = LaserMazeSolver::new(cells, tokens_to_be_added, 3);
+        let mut solver = LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), 3);
 
-        let t0 = time::Instant::now();
         let result = solver.solve();
-        let t1 = time::Instant::now();
 
-        println!("{:?}", result.unwrap().unwrap());
-        println!("Processed in {:?}", t1 - t0);
+        assert!(result.is_ok());
+        let solution = result.unwrap().unwrap();
+
+        assert_solves(&cells, &tokens_to_be_added, 3, &solution);
+    }
+
+    // times each placement order on the hard puzzles. slow, so it's ignored by default; run it with
+    // `cargo test --release benchmark_placement_orders -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn benchmark_placement_orders() {
+        let mut puzzles = vec![];
+
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[3] = Some(Token::target(None, false));
+        cells[6] = Some(Token::target(Some(Orientation::North), true));
+        cells[12] = Some(Token::target(Some(Orientation::South), true));
+        cells[18] = Some(Token::double(None));
+        cells[21] = Some(Token::splitter(None));
+        cells[24] = Some(Token::target(None, false));
+        let tokens_to_be_added = vec![
+            Token::laser(None),
+            Token::target(None, false),
+            Token::splitter(None),
+        ];
+        puzzles.push(("puzzle 54", cells, tokens_to_be_added, 3));
+
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[0] = Some(Token::target(None, false));
+        cells[11] = Some(Token::laser(None));
+        cells[14] = Some(Token::double(None));
+        cells[17] = Some(Token::checkpoint(Some(Orientation::East)));
+        cells[22] = Some(Token::target(None, false));
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+            Token::splitter(None),
+        ];
+        puzzles.push(("puzzle 62", cells, tokens_to_be_added, 2));
+
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[9] = Some(Token::checkpoint(Some(Orientation::North)));
+        cells[11] = Some(Token::splitter(Some(Orientation::North)));
+        cells[13] = Some(Token::double(Some(Orientation::East)));
+        cells[16] = Some(Token::target(Some(Orientation::West), true));
+        cells[18] = Some(Token::blocker());
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+            Token::laser(None),
+        ];
+        puzzles.push(("puzzle 153", cells, tokens_to_be_added, 3));
+
+        let mut cells: [Option<Token>; 25] = Default::default();
+        cells[10] = Some(Token::checkpoint(Some(Orientation::North)));
+        cells[16] = Some(Token::double(Some(Orientation::North)));
+        cells[20] = Some(Token::blocker());
+        cells[23] = Some(Token::laser(None));
+        let tokens_to_be_added = vec![
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::target(None, false),
+            Token::splitter(None),
+            Token::splitter(None),
+        ];
+        puzzles.push(("puzzle 159", cells, tokens_to_be_added, 3));
+
+        for (name, cells, tokens_to_be_added, targets) in puzzles {
+            for placement_order in [
+                PlacementOrder::Spiral,
+                PlacementOrder::RowMajor,
+                PlacementOrder::CenterOut,
+            ] {
+                let mut solver =
+                    LaserMazeSolver::new(cells.clone(), tokens_to_be_added.clone(), targets)
+                        .with_placement_order(placement_order);
+
+                let t0 = time::Instant::now();
+                let result = solver.solve();
+                let t1 = time::Instant::now();
+
+                // every order searches the whole tree, so each finds a solution, if not the same one
+                let solution = result.unwrap().unwrap();
+                assert_eq!(
+                    check_board(solution, targets, &[], &[], &MirrorRules::default()),
+                    Ok(true)
+                );
+                println!("{}, {:?}: {:?}", name, placement_order, t1 - t0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_all() {
+        // too many targets, no target mirror, and a cell blocker to be added
+        let tokens_to_add = vec![Token::laser(None), Token::blocker()];
+        let solver = LaserMazeSolver::new(Default::default(), tokens_to_add, 4);
+        assert_eq!(
+            solver.validate_all(),
+            vec![
+                String::from("Invalid number of targets!"),
+                String::from("Invalid piece count for piece type TargetMirror!"),
+                String::from("Cell Blocker included in tokens_to_be_added!"),
+            ]
+        );
+        // validate stops at the first problem
+        assert_eq!(
+            solver.validate(),
+            Err(String::from("Invalid number of targets!"))
+        );
+
+        let solver = LaserMazeSolver::new(
+            Default::default(),
+            vec![Token::laser(None), Token::target(None, false)],
+            1,
+        );
+        assert!(solver.validate_all().is_empty());
     }
 
     #[test]
@@ -635,7 +2621,7 @@ mod test {
     #[test]
     fn no_laser() {
         // Include a TargetMirror in the test so that we get the error about the laser instead
-        let tokens_to_add = vec![Token::new(TokenType::TargetMirror, None, false)];
+        let tokens_to_add = vec![Token::target(None, false)];
         let mut solver = LaserMazeSolver::new(Default::default(), tokens_to_add, 1);
         let result = solver.solve();
         match result {
@@ -647,7 +2633,7 @@ mod test {
     #[test]
     fn no_target_mirror() {
         // Include a Laser in the test so that we get the error about the laser instead
-        let tokens_to_add = vec![Token::new(TokenType::Laser, None, false)];
+        let tokens_to_add = vec![Token::laser(None)];
         let mut solver = LaserMazeSolver::new(Default::default(), tokens_to_add, 1);
         let result = solver.solve();
         match result {
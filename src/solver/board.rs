@@ -0,0 +1,162 @@
+use crate::solver::orientation::Orientation;
+use crate::solver::token::{Token, TokenType};
+use crate::solver::{rotate_board_cw, token_counts};
+use std::collections::HashMap;
+
+/// Board: the 25 cells of the grid, in the solver's cell coordinates (cell 0 is bottom left, and
+/// cell 5 is north of it)
+#[allow(dead_code)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Board {
+    cells: [Option<Token>; 25],
+}
+
+#[allow(dead_code)]
+impl Board {
+    pub fn new(cells: [Option<Token>; 25]) -> Self {
+        Self { cells }
+    }
+
+    pub fn cells(&self) -> &[Option<Token>; 25] {
+        &self.cells
+    }
+
+    pub fn into_cells(self) -> [Option<Token>; 25] {
+        self.cells
+    }
+
+    /// The token in `cell_index`, or None for an empty cell. Panics if cell_index is more than 24
+    pub fn get(&self, cell_index: usize) -> Option<&Token> {
+        self.cells[cell_index].as_ref()
+    }
+
+    /// Place `token` in `cell_index` (or clear it, for None), returning the token that was there.
+    /// Panics if cell_index is more than 24
+    pub fn set(&mut self, cell_index: usize, token: Option<Token>) -> Option<Token> {
+        std::mem::replace(&mut self.cells[cell_index], token)
+    }
+
+    /// The tokens on the board with their cells, from cell 0 up
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Token)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(|(cell_index, token)| token.as_ref().map(|token| (cell_index, token)))
+    }
+
+    /// The board turned a quarter turn clockwise; see rotate_board_cw
+    pub fn rotate(&self) -> Self {
+        Self::new(rotate_board_cw(&self.cells))
+    }
+
+    /// How many of each type of token is on the board. Every type has an entry, 0 if it isn't there
+    pub fn counts(&self) -> HashMap<TokenType, u8> {
+        token_counts(&self.cells, &[])
+    }
+
+    /// The board as text, the top row first, with one two character cell per column:
+    /// the token (L laser, T target mirror, M target mirror which must be lit, S beam splitter,
+    /// D double mirror, C checkpoint, X cell blocker) and which way it faces (^ > v <, or ? if it
+    /// isn't oriented). An empty cell is "..", and the cell blocker's facing is left blank
+    pub fn render_ascii(&self) -> String {
+        let mut rows = vec![];
+        for row in (0..5).rev() {
+            let cells: Vec<String> = (0..5)
+                .map(|column| match self.get(row * 5 + column) {
+                    Some(token) => render_token(token),
+                    None => String::from(".."),
+                })
+                .collect();
+            rows.push(cells.join(" "));
+        }
+        rows.join("\n")
+    }
+}
+
+impl From<[Option<Token>; 25]> for Board {
+    fn from(cells: [Option<Token>; 25]) -> Self {
+        Self::new(cells)
+    }
+}
+
+fn render_token(token: &Token) -> String {
+    let type_ = match token.type_() {
+        TokenType::Laser => 'L',
+        TokenType::TargetMirror if token.must_light() => 'M',
+        TokenType::TargetMirror => 'T',
+        TokenType::BeamSplitter => 'S',
+        TokenType::DoubleMirror => 'D',
+        TokenType::Checkpoint => 'C',
+        TokenType::CellBlocker => return String::from("X "),
+    };
+    let orientation = match token.orientation() {
+        Some(Orientation::North) => '^',
+        Some(Orientation::East) => '>',
+        Some(Orientation::South) => 'v',
+        Some(Orientation::West) => '<',
+        None => '?',
+    };
+    format!("{}{}", type_, orientation)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_set_iter() {
+        let mut board = Board::default();
+        assert!(board.get(12).is_none());
+        assert_eq!(board.set(12, Some(Token::blocker())), None);
+        assert_eq!(board.set(0, Some(Token::laser(None))), None);
+        assert_eq!(board.get(12), Some(&Token::blocker()));
+        assert_eq!(
+            board.iter().collect::<Vec<(usize, &Token)>>(),
+            vec![(0, &Token::laser(None)), (12, &Token::blocker())]
+        );
+        assert_eq!(board.set(12, None), Some(Token::blocker()));
+        assert_eq!(board.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_rotate_and_counts() {
+        let mut board = Board::default();
+        board.set(0, Some(Token::laser(Some(Orientation::North))));
+        board.set(1, Some(Token::target(None, true)));
+        board.set(2, Some(Token::target(None, false)));
+
+        let rotated = board.rotate();
+        assert_eq!(rotated.cells(), &rotate_board_cw(board.cells()));
+        // the bottom left corner is the top left after a clockwise turn
+        assert_eq!(
+            rotated.get(20),
+            Some(&Token::laser(Some(Orientation::East)))
+        );
+        assert_eq!(rotated.rotate().rotate().rotate(), board);
+
+        let counts = board.counts();
+        assert_eq!(counts[&TokenType::Laser], 1);
+        assert_eq!(counts[&TokenType::TargetMirror], 2);
+        assert_eq!(counts[&TokenType::CellBlocker], 0);
+    }
+
+    #[test]
+    fn test_render_ascii() {
+        let mut board = Board::default();
+        board.set(0, Some(Token::laser(Some(Orientation::North))));
+        board.set(12, Some(Token::blocker()));
+        board.set(20, Some(Token::target(Some(Orientation::East), true)));
+        board.set(24, Some(Token::splitter(None)));
+        assert_eq!(
+            board.render_ascii(),
+            [
+                "M> .. .. .. S?",
+                ".. .. .. .. ..",
+                ".. .. X  .. ..",
+                ".. .. .. .. ..",
+                "L^ .. .. .. ..",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -1,6 +1,45 @@
+use crate::solver::orientation::Orientation;
 use crate::solver::solver_node::active_laser::ActiveLaser;
-use crate::solver::solver_node::{SolverNode, SPIRAL_ORDER_REVERSE};
+use crate::solver::solver_node::SolverNode;
 use crate::solver::token::{LaserTokenInteractionResult, Token, TokenType};
+use crate::solver::{cell_name, laser_state};
+use std::fmt;
+
+// the default for how many steps the lasers may take across the board. the visited cells and directions
+// already stop official pieces looping, and no official board takes more than about 100 steps;
+// this is a backstop for custom pieces
+pub const MAX_LASER_STEPS: usize = 1000;
+
+/// Reasons a board can't be checked
+#[derive(Clone, Debug, PartialEq)]
+pub enum CheckError {
+    // there is no laser on the board
+    LaserMissing,
+    // the token at this cell index has no orientation set
+    UnorientedToken(usize),
+    // the lasers were still moving after this many steps
+    TooManySteps(usize),
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckError::LaserMissing => write!(f, "There is no laser on the board!"),
+            CheckError::UnorientedToken(cell_index) => {
+                write!(
+                    f,
+                    "The token in cell {} has no orientation set!",
+                    cell_name(*cell_index)
+                )
+            }
+            CheckError::TooManySteps(max_steps) => write!(
+                f,
+                "The laser was still moving after {} steps; does it loop forever?",
+                max_steps
+            ),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Checker {
@@ -8,8 +47,25 @@ pub struct Checker {
     // there can be 4 active lasers if 2 perpindicular lasers hit the same beam splitter
     active_lasers: [Option<ActiveLaser>; 4],
     laser_visited: [[bool; 4]; 25],
+    // the cells where a laser stopped at an oriented token: a lit target, or a side which rejects it
+    laser_absorbed: [bool; 25],
     unoriented_occupied_cells: Vec<usize>,
     all_lasers_remain_on_board: bool,
+    // the cell and direction of the first laser to leave the board
+    off_board_exit: Option<(usize, Orientation)>,
+    // a laser came back to a wall-side of the laser token; the board can't be solved, but the beam
+    // stays on the board
+    beam_hit_laser_side: bool,
+    // when recording, the cells holding a token which a laser entered, and the laser's direction.
+    // off by default, since the solver checks many boards and doesn't need it
+    record_beam_path: bool,
+    beam_path: Vec<(usize, Orientation)>,
+    // .check() gives up with Err after moving the lasers this many times
+    max_steps: usize,
+    // if Some, .check() stops with Ok after moving the lasers this many times
+    stop_after_steps: Option<usize>,
+    // the step on which a laser first left the board, or hit a side of a token which rejects it
+    failed_at_step: Option<usize>,
 }
 
 impl Default for Checker {
@@ -19,22 +75,42 @@ impl Default for Checker {
         let laser_visited: [[bool; 4]; 25] = Default::default();
         let unoriented_occupied_cells: Vec<usize> = Default::default();
         let all_lasers_remain_on_board = true;
+        let off_board_exit = None;
 
         Self {
             grid,
             active_lasers,
             laser_visited,
+            laser_absorbed: [false; 25],
             unoriented_occupied_cells,
             all_lasers_remain_on_board,
+            off_board_exit,
+            beam_hit_laser_side: false,
+            record_beam_path: false,
+            beam_path: vec![],
+            max_steps: MAX_LASER_STEPS,
+            stop_after_steps: None,
+            failed_at_step: None,
         }
     }
 }
 
 impl Checker {
-    pub fn check(mut self) -> Self {
-        self.initialize();
+    // march the lasers across the board. tokens other than the laser may be unoriented; lasers stop
+    // at them and the cells are recorded
+    pub fn check(mut self) -> Result<Self, CheckError> {
+        self.initialize()?;
 
+        let mut steps = 0;
         while self.has_active_lasers() {
+            if self.stop_after_steps == Some(steps) {
+                break;
+            }
+            if steps == self.max_steps {
+                return Err(CheckError::TooManySteps(self.max_steps));
+            }
+            steps += 1;
+
             // inner loop: iterate on lasers and do some work on Some()s until no more active lasers
             let mut new_laser_index = 0;
             let mut new_lasers = [None, None, None, None];
@@ -51,9 +127,14 @@ impl Checker {
                             continue;
                         }
 
+                        if self.record_beam_path {
+                            self.beam_path
+                                .push((next_laser_position, laser.orientation.clone()));
+                        }
+
                         // if the piece is oriented, continue marching the laser
                         for new_laser_direction in token
-                            .outbound_lasers_given_inbound_laser_direction(&laser.orientation)
+                            .outbound_lasers_with_rules(&laser.orientation, &self.grid.mirror_rules)
                             .into_iter()
                         {
                             match new_laser_direction {
@@ -66,7 +147,7 @@ impl Checker {
                                     self.laser_visited[next_laser_position]
                                         [orientation.to_index()] = true;
                                     if new_laser_index > 3 {
-                                        println!("panic config: {:?}", self);
+                                        log::error!("panic config: {:?}", self);
                                         panic!("laser index > 3!");
                                     }
                                     let new_active_laser = ActiveLaser {
@@ -84,9 +165,16 @@ impl Checker {
                                     }
                                 }
                                 LaserTokenInteractionResult::NoOutboundLaser { valid } => {
+                                    self.laser_absorbed[next_laser_position] = true;
                                     match valid {
                                         true => continue,
-                                        false => self.all_lasers_remain_on_board = false, // TODO this variable name is now misleading
+                                        false => {
+                                            self.all_lasers_remain_on_board = false; // TODO this variable name is now misleading
+                                            self.failed_at_step.get_or_insert(steps);
+                                            if token.type_() == &TokenType::Laser {
+                                                self.beam_hit_laser_side = true;
+                                            }
+                                        }
                                     }
                                 }
                             }
@@ -95,7 +183,7 @@ impl Checker {
                         self.laser_visited[next_laser_position][laser.orientation.to_index()] =
                             true;
                         if new_laser_index > 3 {
-                            println!("panic config: {:?}", self);
+                            log::error!("panic config: {:?}", self);
                             panic!("laser index > 3!!");
                         }
                         new_lasers[new_laser_index] = Some(ActiveLaser {
@@ -106,12 +194,16 @@ impl Checker {
                     }
                 } else {
                     self.all_lasers_remain_on_board = false;
+                    self.failed_at_step.get_or_insert(steps);
+                    if self.off_board_exit.is_none() {
+                        self.off_board_exit = Some((laser.cell_index, laser.orientation.clone()));
+                    }
                 }
             }
             self.active_lasers = new_lasers;
         }
 
-        self
+        Ok(self)
     }
 
     fn remaining_tokens_to_be_added(&self) -> bool {
@@ -127,7 +219,15 @@ impl Checker {
         // - if the lasers didn't visit unoriented tokens, and not all tokens are placed,
         //     new branches will be made for placing the next token in any cell the laser visited
 
-        self = self.check();
+        self = match self.check() {
+            Ok(checker) => checker,
+            // a beam which never settles can't be part of a solution
+            Err(CheckError::TooManySteps(_)) => return Err(vec![]),
+            Err(e) => panic!(
+                "The laser is placed and rotated before generating branches from a checker: {}",
+                e
+            ),
+        };
         if self.solved() {
             self.grid.reset_tokens();
             Ok(self.grid.cells.clone())
@@ -148,21 +248,27 @@ impl Checker {
             // if the laser only hit oriented tokens, try placing the next token in any of the cells the laser visited but are not occupied by a token
             let empty_cells_with_active_laser = self.empty_cells_with_active_laser();
             let mut result = vec![];
-            for i in SPIRAL_ORDER_REVERSE.iter() {
-                if !empty_cells_with_active_laser.contains(i) {
+            for i in self.grid.placement_order.push_order() {
+                if !empty_cells_with_active_laser.contains(&i) {
                     continue;
                 }
                 let mut new_node = self.grid.clone();
-                new_node.cells[*i] = Some(token.clone());
+                new_node.cells[i] = Some(token.clone());
                 result.push(new_node);
             }
             result
         } else {
             // this board isn't solved, and doesn't have any new children
+            log::trace!("At a leaf!");
             vec![]
         }
     }
 
+    // the cells of the board; after .check(), tokens hold the lit state from tracing the laser
+    pub fn cells(&self) -> &[Option<Token>; 25] {
+        &self.grid.cells
+    }
+
     pub fn from_solver_node(solver_node: SolverNode) -> Self {
         Self {
             grid: solver_node,
@@ -170,8 +276,8 @@ impl Checker {
         }
     }
 
-    #[allow(dead_code)]
-    fn cells_with_active_laser(&self) -> Vec<usize> {
+    // return the indices of cells the laser has visited, with or without a token
+    pub fn cells_with_active_laser(&self) -> Vec<usize> {
         let mut result = vec![];
         for (idx, cell) in self.laser_visited.into_iter().enumerate() {
             if cell[0] || cell[1] || cell[2] || cell[3] {
@@ -181,6 +287,80 @@ impl Checker {
         result
     }
 
+    // the cell a laser left the board from, and the direction it was going
+    pub fn off_board_exit(&self) -> Option<(usize, Orientation)> {
+        self.off_board_exit.clone()
+    }
+
+    // give up on .check() after moving the lasers `max_steps` times
+    #[allow(dead_code)]
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    // stop .check() after moving the lasers `steps` times, leaving the lasers where they got to
+    pub fn stopping_after(mut self, steps: usize) -> Self {
+        self.stop_after_steps = Some(steps);
+        self
+    }
+
+    // the step (counting from 1) on which a laser first left the board or hit a side of a token
+    // which rejects it; None if no laser did
+    pub fn failed_at_step(&self) -> Option<usize> {
+        self.failed_at_step
+    }
+
+    // how many times a laser crossed or left a cell, counting each direction in a cell once; longer
+    // paths make for more interesting puzzles
+    #[allow(dead_code)]
+    pub fn path_length(&self) -> usize {
+        self.laser_visited
+            .iter()
+            .flatten()
+            .filter(|visited| **visited)
+            .count()
+    }
+
+    // whether a laser came back to a wall-side of the laser token, rather than into its front
+    pub fn beam_hit_laser_side(&self) -> bool {
+        self.beam_hit_laser_side
+    }
+
+    // record the beam path during .check()
+    pub fn recording_beam_path(mut self) -> Self {
+        self.record_beam_path = true;
+        self
+    }
+
+    // the cells holding an oriented token which a laser entered, in order, with the laser's direction;
+    // empty unless built with .recording_beam_path()
+    pub fn beam_path(&self) -> &[(usize, Orientation)] {
+        &self.beam_path
+    }
+
+    // for each cell, the directions a laser travelled through it or left it in, indexed by
+    // Orientation::to_index()
+    pub fn laser_visited(&self) -> &[[bool; 4]; 25] {
+        &self.laser_visited
+    }
+
+    // for each cell, whether it holds a token the laser touched; meaningful after .check(). unlike
+    // the tokens' lit state, a cell blocker or laser the laser never reached isn't counted
+    pub fn lit_map(&self) -> [bool; 25] {
+        let reached = self.cells_reached();
+        core::array::from_fn(|i| reached[i] && self.grid.cells[i].is_some())
+    }
+
+    // for each cell, whether a laser reached it: crossed or left it, or stopped at its token
+    pub fn cells_reached(&self) -> [bool; 25] {
+        core::array::from_fn(|i| {
+            self.laser_visited[i].iter().any(|visited| *visited)
+                || self.laser_absorbed[i]
+                || self.unoriented_occupied_cells.contains(&i)
+        })
+    }
+
     // return the indices of cells where the laser has visited but there is no token
     fn empty_cells_with_active_laser(&self) -> Vec<usize> {
         let mut result = vec![];
@@ -201,9 +381,43 @@ impl Checker {
             && self.all_required_targets_lit()
             && self.all_tokens_lit()
             && self.all_lasers_remain_on_board
+            && self.all_waypoints_visited()
+            && !self.any_forbidden_cell_visited()
             && !self.remaining_tokens_to_be_added()
     }
 
+    /// Partial credit: how many of the targets the puzzle asks for are lit, even if the board isn't
+    /// solved. An unlit target which must be lit takes one of the puzzle's targets, so lighting other
+    /// targets instead doesn't count for it
+    #[allow(dead_code)]
+    pub fn score(&self) -> u8 {
+        let required_targets_unlit = self
+            .grid
+            .cells
+            .iter()
+            .flatten()
+            .filter(|token| token.must_light() && token.target_lit() == Some(false))
+            .count() as u8;
+        self.count_lit_targets()
+            .min(self.grid.targets.saturating_sub(required_targets_unlit))
+    }
+
+    fn all_waypoints_visited(&self) -> bool {
+        let reached = self.cells_reached();
+        self.grid
+            .waypoints
+            .iter()
+            .all(|cell_index| reached[*cell_index])
+    }
+
+    fn any_forbidden_cell_visited(&self) -> bool {
+        let reached = self.cells_reached();
+        self.grid
+            .forbidden_cells
+            .iter()
+            .any(|cell_index| reached[*cell_index])
+    }
+
     fn count_lit_targets(&self) -> u8 {
         self.grid
             .cells
@@ -245,33 +459,25 @@ impl Checker {
     }
 
     // Find the laser piece and set initialize the active laser there
-    fn initialize(&mut self) {
-        for i in 0..25 {
-            if let Some(token) = &self.grid.cells[i] {
-                if token.type_() == &TokenType::Laser {
-                    self.laser_visited[i][token
-                        .orientation()
-                        .expect("Tried running checker on piece without orientation set")
-                        .to_index()] = true;
-                    let initial_active_laser = ActiveLaser {
-                        orientation: token
-                            .orientation()
-                            .expect("Tried running checker on piece without orientation set")
-                            .clone(),
-                        cell_index: i,
-                    };
-                    self.active_lasers[0] = Some(initial_active_laser);
-                    return;
-                }
-            }
-        }
+    fn initialize(&mut self) -> Result<(), CheckError> {
+        let (i, orientation) = laser_state(&self.grid.cells).ok_or(CheckError::LaserMissing)?;
+        let orientation = orientation.ok_or(CheckError::UnorientedToken(i))?;
+        self.laser_visited[i][orientation.to_index()] = true;
+        let initial_active_laser = ActiveLaser {
+            orientation,
+            cell_index: i,
+        };
+        self.active_lasers[0] = Some(initial_active_laser);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::solver::orientation::{self, Orientation};
+    use crate::solver::orientation::Orientation;
+    use crate::solver::solver_node::PlacementOrder;
+    use crate::solver::token::MirrorRules;
 
     #[test]
     fn test_solver_puzzle_62_debug() {
@@ -282,11 +488,7 @@ mod test {
         // light, no lasers go off board), but there is still a remaining token to be added!
         let node = SolverNode {
             cells: [
-                Some(Token::new(
-                    TokenType::TargetMirror,
-                    Some(Orientation::North),
-                    false,
-                )),
+                Some(Token::target(Some(Orientation::North), false)),
                 None,
                 None,
                 None,
@@ -297,54 +499,30 @@ mod test {
                 None,
                 None,
                 None,
-                Some(Token::new(TokenType::Laser, Some(Orientation::East), false)),
+                Some(Token::laser(Some(Orientation::East))),
                 None,
-                Some(Token::new(
-                    TokenType::BeamSplitter,
-                    Some(Orientation::East),
-                    false,
-                )),
-                Some(Token::new(
-                    TokenType::DoubleMirror,
-                    Some(Orientation::East),
-                    false,
-                )),
-                Some(Token::new(
-                    TokenType::TargetMirror,
-                    Some(Orientation::West),
-                    false,
-                )),
+                Some(Token::splitter(Some(Orientation::East))),
+                Some(Token::double(Some(Orientation::East))),
+                Some(Token::target(Some(Orientation::West), false)),
                 None,
-                Some(Token::new(
-                    TokenType::Checkpoint,
-                    Some(Orientation::East),
-                    false,
-                )),
+                Some(Token::checkpoint(Some(Orientation::East))),
                 None,
-                Some(Token::new(
-                    TokenType::TargetMirror,
-                    Some(Orientation::North),
-                    false,
-                )),
+                Some(Token::target(Some(Orientation::North), false)),
                 None,
                 None,
-                Some(Token::new(
-                    TokenType::TargetMirror,
-                    Some(Orientation::East),
-                    false,
-                )),
-                Some(Token::new(
-                    TokenType::TargetMirror,
-                    Some(Orientation::North),
-                    false,
-                )),
+                Some(Token::target(Some(Orientation::East), false)),
+                Some(Token::target(Some(Orientation::North), false)),
                 None,
             ],
             tokens_to_be_added: vec![],
-            tokens_to_be_added_shuffled: vec![Token::new(TokenType::BeamSplitter, None, false)],
+            tokens_to_be_added_shuffled: vec![Token::splitter(None)],
             targets: 2,
+            waypoints: vec![],
+            forbidden_cells: vec![],
+            mirror_rules: MirrorRules::default(),
+            placement_order: PlacementOrder::default(),
         };
-        let checker = node.check();
+        let checker = node.check().unwrap();
         println!("Checker after running node.check():\n{:?}\n---", checker);
         assert!(checker.remaining_tokens_to_be_added());
         assert!(!checker.solved());
@@ -354,25 +532,13 @@ mod test {
     fn test_checker_simple() {
         let node = SolverNode {
             cells: [
-                Some(Token::new(TokenType::Laser, Some(Orientation::East), false)),
-                Some(Token::new(
-                    TokenType::BeamSplitter,
-                    Some(Orientation::West),
-                    false,
-                )),
-                Some(Token::new(
-                    TokenType::TargetMirror,
-                    Some(Orientation::West),
-                    false,
-                )),
+                Some(Token::laser(Some(Orientation::East))),
+                Some(Token::splitter(Some(Orientation::West))),
+                Some(Token::target(Some(Orientation::West), false)),
                 None,
                 None,
                 None,
-                Some(Token::new(
-                    TokenType::TargetMirror,
-                    Some(Orientation::South),
-                    false,
-                )),
+                Some(Token::target(Some(Orientation::South), false)),
                 None,
                 None,
                 None,
@@ -395,8 +561,255 @@ mod test {
             tokens_to_be_added: vec![],
             tokens_to_be_added_shuffled: vec![],
             targets: 2,
+            waypoints: vec![],
+            forbidden_cells: vec![],
+            mirror_rules: MirrorRules::default(),
+            placement_order: PlacementOrder::default(),
         };
-        let checker = node.check();
+        let checker = node.check().unwrap();
         assert!(checker.solved());
     }
+
+    #[test]
+    fn test_checker_splitter_lit_on_both_axes() {
+        // the splitter in cell 5 sends the beam north and east, and the double mirrors in cells 10
+        // and 6 turn both beams into cell 11 on the same step: one heading east, one heading north
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::North)));
+        node.cells[5] = Some(Token::splitter(Some(Orientation::East)));
+        node.cells[6] = Some(Token::double(Some(Orientation::East)));
+        node.cells[10] = Some(Token::double(Some(Orientation::East)));
+        // each beam passes through the splitter in cell 11 and is reflected: north, west, east and
+        // south, four beams at once
+        node.cells[11] = Some(Token::splitter(Some(Orientation::North)));
+        let checker = Checker::from_solver_node(node.clone())
+            .recording_beam_path()
+            .check()
+            .unwrap();
+        assert_eq!(checker.laser_visited()[11], [true; 4]);
+        assert_eq!(
+            checker.beam_path()[2..5],
+            [
+                (10, Orientation::North),
+                (11, Orientation::North),
+                (11, Orientation::East),
+            ]
+        );
+        // none of the four is dropped: each carries on out of cell 11
+        assert!(checker.laser_visited()[16][Orientation::North.to_index()]);
+        assert!(checker.laser_visited()[12][Orientation::East.to_index()]);
+        assert!(checker.beam_path().contains(&(10, Orientation::West)));
+        assert!(checker.beam_path().contains(&(6, Orientation::South)));
+
+        // turned the other way, the splitter reflects each beam along the other's pass through
+        // beam, so the four outbound beams are two pairs of duplicates, and only one of each is kept
+        node.cells[11] = Some(Token::splitter(Some(Orientation::East)));
+        let checker = Checker::from_solver_node(node)
+            .recording_beam_path()
+            .check()
+            .unwrap();
+        assert_eq!(checker.laser_visited()[11], [true, true, false, false]);
+        assert!(checker.laser_visited()[16][Orientation::North.to_index()]);
+        assert!(checker.laser_visited()[12][Orientation::East.to_index()]);
+        assert_eq!(checker.beam_path().len(), 5);
+    }
+
+    #[test]
+    fn test_checker_splitter_next_to_laser() {
+        // the laser fires straight into the splitter in cell 5, so there are two beams after the
+        // first step: one going on north, and one reflected east
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::North)));
+        node.cells[5] = Some(Token::splitter(Some(Orientation::East)));
+        let checker = Checker::from_solver_node(node)
+            .recording_beam_path()
+            .check()
+            .unwrap();
+        assert_eq!(checker.beam_path(), [(5, Orientation::North)]);
+        assert_eq!(checker.laser_visited()[5], [true, true, false, false]);
+        for cell_index in [10, 15, 20] {
+            assert!(checker.laser_visited()[cell_index][Orientation::North.to_index()]);
+        }
+        for cell_index in [6, 7, 8, 9] {
+            assert!(checker.laser_visited()[cell_index][Orientation::East.to_index()]);
+        }
+        // the laser's own cell is marked where the beam starts, and neither beam comes back to it
+        assert_eq!(checker.laser_visited()[0], [true, false, false, false]);
+        assert!(!checker.beam_hit_laser_side());
+        // the north beam is a step shorter, so it leaves the board first
+        assert_eq!(checker.off_board_exit(), Some((20, Orientation::North)));
+        assert_eq!(checker.failed_at_step(), Some(5));
+    }
+
+    #[test]
+    fn test_checker_checkpoint_blocking_face_unlit() {
+        // the laser hits the checkpoint's blocking face, and nothing else is on the board
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::East)));
+        node.cells[2] = Some(Token::checkpoint(Some(Orientation::North)));
+        let checker = node.check().unwrap();
+        assert!(!checker.cells()[2].as_ref().unwrap().lit);
+        assert!(!checker.all_tokens_lit());
+        assert!(!checker.solved());
+    }
+
+    #[test]
+    fn test_checker_off_board_exit() {
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::North)));
+        node.cells[10] = Some(Token::double(Some(Orientation::East)));
+        let checker = node.check().unwrap();
+        assert_eq!(checker.off_board_exit(), Some((14, Orientation::East)));
+
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::North)));
+        node.cells[10] = Some(Token::target(Some(Orientation::South), false));
+        let checker = node.check().unwrap();
+        assert_eq!(checker.off_board_exit(), None);
+    }
+
+    #[test]
+    fn test_checker_score() {
+        // the laser lights the target in cell 10, and misses the one in cell 4
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::North)));
+        node.cells[10] = Some(Token::target(Some(Orientation::South), false));
+        node.cells[4] = Some(Token::target(Some(Orientation::South), false));
+        node.targets = 2;
+        let checker = node.clone().check().unwrap();
+        assert!(!checker.solved());
+        assert_eq!(checker.score(), 1);
+
+        // an unlit target which must be lit leaves no room for the lit one
+        node.cells[4] = Some(Token::target(Some(Orientation::South), true));
+        node.targets = 1;
+        assert_eq!(node.check().unwrap().score(), 0);
+    }
+
+    #[test]
+    fn test_checker_beam_hit_laser_side() {
+        // the laser fires north, and double mirrors turn the beam back around into the laser's
+        // east side
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::North)));
+        node.cells[10] = Some(Token::double(Some(Orientation::East)));
+        node.cells[11] = Some(Token::double(Some(Orientation::North)));
+        node.cells[1] = Some(Token::double(Some(Orientation::East)));
+        let checker = node.clone().check().unwrap();
+        assert!(checker.beam_hit_laser_side());
+        assert_eq!(checker.off_board_exit(), None);
+
+        // a beam leaving the board isn't hitting the laser
+        node.cells[1] = None;
+        let checker = node.check().unwrap();
+        assert!(!checker.beam_hit_laser_side());
+        assert_eq!(checker.off_board_exit(), Some((1, Orientation::South)));
+    }
+
+    #[test]
+    fn test_checker_path_length() {
+        // the laser's own cell and 4 more up the column
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::North)));
+        assert_eq!(node.clone().check().unwrap().path_length(), 5);
+
+        // a splitter in cell 10 also sends the beam east, out of cell 10 and across 4 more
+        node.cells[10] = Some(Token::splitter(Some(Orientation::East)));
+        assert_eq!(node.check().unwrap().path_length(), 10);
+    }
+
+    #[test]
+    fn test_checker_lit_map() {
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::North)));
+        node.cells[10] = Some(Token::target(Some(Orientation::South), false));
+        // off the beam's path
+        node.cells[3] = Some(Token::target(Some(Orientation::West), false));
+        node.cells[4] = Some(Token::blocker());
+        let lit_map = node.check().unwrap().lit_map();
+        let mut expected = [false; 25];
+        expected[0] = true;
+        expected[10] = true;
+        assert_eq!(lit_map, expected);
+    }
+
+    #[test]
+    fn test_checker_max_steps() {
+        // the laser crosses 4 cells, and takes a 5th step off the board
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(Some(Orientation::North)));
+        let checker = Checker::from_solver_node(node.clone()).with_max_steps(3);
+        assert_eq!(checker.check().unwrap_err(), CheckError::TooManySteps(3));
+        let checker = Checker::from_solver_node(node).with_max_steps(5);
+        assert_eq!(
+            checker.check().unwrap().off_board_exit(),
+            Some((20, Orientation::North))
+        );
+    }
+
+    #[test]
+    fn test_checker_random_boards_never_panic() {
+        use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+        // a fixed seed, so a failure can be reproduced
+        let mut rng = StdRng::seed_from_u64(1131);
+        let mut cell_indices: Vec<usize> = (0..25).collect();
+        for _ in 0..10_000 {
+            // as many of each piece as a valid puzzle may have, in random cells
+            let mut tokens = vec![Token::laser(None)];
+            for _ in 0..rng.gen_range(1..6) {
+                tokens.push(Token::target(None, rng.gen_bool(0.3)));
+            }
+            for _ in 0..rng.gen_range(0..3) {
+                tokens.push(Token::splitter(None));
+            }
+            if rng.gen_bool(0.5) {
+                tokens.push(Token::double(None));
+            }
+            if rng.gen_bool(0.5) {
+                tokens.push(Token::checkpoint(None));
+            }
+            if rng.gen_bool(0.5) {
+                tokens.push(Token::blocker());
+            }
+            cell_indices.shuffle(&mut rng);
+
+            let mut node = SolverNode::default();
+            for (token, cell_index) in tokens.into_iter().zip(&cell_indices) {
+                let mut token = token;
+                if token.type_() != &TokenType::CellBlocker {
+                    token.orientation = Some(Orientation::from_index(rng.gen_range(0..4)));
+                }
+                node.cells[*cell_index] = Some(token);
+            }
+            node.targets = rng.gen_range(1..=3);
+
+            // the board may well be unsolved, but checking it mustn't panic
+            let checker = node.clone().check().unwrap();
+            checker.solved();
+            checker.score();
+            Checker::from_solver_node(node).generate_branches().ok();
+        }
+    }
+
+    #[test]
+    fn test_checker_laser_missing() {
+        let mut node = SolverNode::default();
+        node.cells[6] = Some(Token::target(Some(Orientation::South), false));
+        assert_eq!(node.check().unwrap_err(), CheckError::LaserMissing);
+    }
+
+    #[test]
+    fn test_checker_laser_unoriented() {
+        let mut node = SolverNode::default();
+        node.cells[1] = Some(Token::laser(None));
+        node.cells[6] = Some(Token::target(Some(Orientation::South), false));
+        let error = node.check().unwrap_err();
+        assert_eq!(error, CheckError::UnorientedToken(1));
+        // named as on the card, not by cell index
+        assert_eq!(
+            error.to_string(),
+            "The token in cell E2 has no orientation set!"
+        );
+    }
 }
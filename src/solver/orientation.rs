@@ -43,6 +43,24 @@ impl Orientation {
         self.reorient_by_offset(outbound_orientation.to_index())
     }
 
+    // a quarter turn clockwise
+    pub fn rotate_cw(&self) -> Self {
+        self.reorient_by_offset(1)
+    }
+
+    // mirrored left to right: East and West swap
+    pub fn reflect_horizontal(&self) -> Self {
+        match self {
+            Self::East => Self::West,
+            Self::West => Self::East,
+            _ => self.clone(),
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        self.reorient_by_offset(2)
+    }
+
     fn reorient_by_offset(&self, offset: usize) -> Self {
         let self_orientation_ordinal_value = self.to_index();
         let idx = (self_orientation_ordinal_value + offset) % 4;
@@ -0,0 +1,479 @@
+use crate::solver::orientation::Orientation;
+use crate::solver::token::{Token, TokenType};
+use crate::solver::LaserMazeSolver;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// PuzzleDef: a challenge card, in the solver's cell coordinates (cell 0 is bottom left)
+/// id: the challenge number
+/// grid: the tokens initially placed on the grid
+/// to_be_added: the "add to grid" section of the card
+/// targets: the number of targets which must be lit
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct PuzzleDef {
+    pub id: u32,
+    pub grid: [Option<Token>; 25],
+    pub to_be_added: Vec<Token>,
+    pub targets: u8,
+}
+
+impl PuzzleDef {
+    #[allow(dead_code)]
+    pub fn solver(&self) -> LaserMazeSolver {
+        LaserMazeSolver::new(self.grid.clone(), self.to_be_added.clone(), self.targets)
+    }
+
+    /// Place `token` (or clear the cell, for None) by its position as printed on the card: row 0 is the
+    /// top row (A), column 0 is the left column (1). So "C3", the center cell, is set_cell(2, 2, ..).
+    /// Panics if row or col is more than 4
+    #[allow(dead_code)]
+    pub fn set_cell(&mut self, row: usize, col: usize, token: Option<Token>) {
+        assert!(row < 5 && col < 5, "Invalid cell position!");
+        // model cell 0 is bottom left
+        self.grid[(4 - row) * 5 + col] = token;
+    }
+}
+
+/// Parse a table of challenges. Each non-empty line that doesn't start with `#` describes one token,
+/// with comma or tab separated columns:
+///
+/// `challenge, targets, placement, cell, type, orientation, must_light`
+///
+/// - challenge: the challenge number; rows with the same number make up one challenge
+/// - targets: the number of targets to light (1-3); must agree for every row of a challenge
+/// - placement: `grid` for tokens on the board, `add` for tokens to be added
+/// - cell: the cell index (0-24, 0 is bottom left) for `grid` tokens; empty for `add` tokens
+/// - type: `Laser`, `TargetMirror`, `BeamSplitter`, `DoubleMirror`, `Checkpoint` or `CellBlocker`
+/// - orientation: `North`, `East`, `South`, `West`, or empty if not given on the card
+/// - must_light: `true` or `false` (or empty); only a `TargetMirror` may be `true`
+///
+/// Challenges are returned in the order they first appear. Returns Err(s) for the first invalid row;
+/// s names the line and the problem
+#[allow(dead_code)]
+pub fn parse_challenge_table(csv: &str) -> Result<Vec<PuzzleDef>, String> {
+    let mut result: Vec<PuzzleDef> = vec![];
+
+    for (line_index, line) in csv.lines().enumerate() {
+        let line_number = line_index + 1;
+        // don't trim the line itself; trailing tabs separate empty columns
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let columns = line
+            .split([',', '\t'])
+            .map(|column| column.trim())
+            .collect::<Vec<&str>>();
+        if columns.len() != 7 {
+            return Err(format!(
+                "Line {}: expected 7 columns, found {}!",
+                line_number,
+                columns.len()
+            ));
+        }
+
+        let id: u32 = columns[0]
+            .parse()
+            .map_err(|_| format!("Line {}: invalid challenge number!", line_number))?;
+        let targets: u8 = match columns[1].parse() {
+            Ok(targets @ 1..=3) => targets,
+            _ => return Err(format!("Line {}: invalid number of targets!", line_number)),
+        };
+        let type_ = parse_token_type(columns[4])
+            .ok_or_else(|| format!("Line {}: invalid token type!", line_number))?;
+        let orientation = match columns[5] {
+            "" => None,
+            s => Some(
+                parse_orientation(s)
+                    .ok_or_else(|| format!("Line {}: invalid orientation!", line_number))?,
+            ),
+        };
+        let must_light = match columns[6] {
+            "" | "false" => false,
+            "true" => true,
+            _ => return Err(format!("Line {}: invalid must_light!", line_number)),
+        };
+        if must_light && type_ != TokenType::TargetMirror {
+            return Err(format!(
+                "Line {}: only a TargetMirror may be must_light!",
+                line_number
+            ));
+        }
+        let token = Token::new(type_, orientation, must_light);
+
+        let puzzle_def = match result.iter_mut().position(|puzzle_def| puzzle_def.id == id) {
+            Some(idx) => &mut result[idx],
+            None => {
+                result.push(PuzzleDef {
+                    id,
+                    grid: Default::default(),
+                    to_be_added: vec![],
+                    targets,
+                });
+                result.last_mut().expect("We just pushed a PuzzleDef")
+            }
+        };
+        if puzzle_def.targets != targets {
+            return Err(format!(
+                "Line {}: number of targets disagrees with earlier rows of challenge {}!",
+                line_number, id
+            ));
+        }
+
+        match columns[2] {
+            "grid" => {
+                let cell_index = match columns[3].parse::<usize>() {
+                    Ok(cell_index @ 0..=24) => cell_index,
+                    _ => return Err(format!("Line {}: invalid cell index!", line_number)),
+                };
+                if puzzle_def.grid[cell_index].is_some() {
+                    return Err(format!(
+                        "Line {}: cell {} is already occupied!",
+                        line_number, cell_index
+                    ));
+                }
+                puzzle_def.grid[cell_index] = Some(token);
+            }
+            "add" => {
+                if !columns[3].is_empty() {
+                    return Err(format!(
+                        "Line {}: tokens to be added can't have a cell index!",
+                        line_number
+                    ));
+                }
+                // the card has room for 6 tokens to be added
+                if puzzle_def.to_be_added.len() == 6 {
+                    return Err(format!(
+                        "Line {}: too many tokens to be added for challenge {}!",
+                        line_number, id
+                    ));
+                }
+                puzzle_def.to_be_added.push(token);
+            }
+            _ => return Err(format!("Line {}: invalid placement!", line_number)),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Whether `solution` is a finished board for `puzzle`: every token is oriented, each token fixed on
+/// the grid is still in its cell (keeping its orientation, if the card gives one), and the tokens in
+/// the other cells are exactly the tokens to be added. Tokens are matched by type and whether they
+/// must be lit; their lit state doesn't matter. Doesn't check that the board lights the targets
+#[allow(dead_code)]
+pub fn solution_matches_puzzle(puzzle: &PuzzleDef, solution: &[Option<Token>; 25]) -> bool {
+    let same_token =
+        |a: &Token, b: &Token| a.type_() == b.type_() && a.must_light() == b.must_light();
+
+    let mut added = vec![];
+    for (fixed, placed) in puzzle.grid.iter().zip(solution.iter()) {
+        match (fixed, placed) {
+            (_, Some(placed)) if placed.orientation().is_none() => return false,
+            (Some(fixed), Some(placed)) => {
+                if !same_token(fixed, placed)
+                    || fixed
+                        .orientation()
+                        .is_some_and(|orientation| Some(orientation) != placed.orientation())
+                {
+                    return false;
+                }
+            }
+            (Some(_), None) => return false,
+            (None, Some(placed)) => added.push(placed),
+            (None, None) => {}
+        }
+    }
+
+    // a token to be added which the card already orients can only be matched by a token facing
+    // that way, so those are matched first; an unoriented one matches any orientation
+    let mut to_be_added: Vec<&Token> = puzzle.to_be_added.iter().collect();
+    to_be_added.sort_by_key(|token| token.orientation().is_none());
+    for token in to_be_added {
+        match added.iter().position(|placed| {
+            same_token(token, placed)
+                && (token.orientation().is_none() || token.orientation() == placed.orientation())
+        }) {
+            Some(i) => {
+                added.swap_remove(i);
+            }
+            None => return false,
+        }
+    }
+    added.is_empty()
+}
+
+/// BatchResult: one puzzle's outcome from solve_batch: its index in the batch, what .solve()
+/// returned for it (Err(s) for an invalid puzzle), and how long the solve took
+#[allow(dead_code)]
+pub type BatchResult = (usize, Result<Option<[Option<Token>; 25]>, String>, Duration);
+
+/// Solve each of `puzzles`, spread across one thread per core. Returns a BatchResult for each
+/// puzzle, in the order of `puzzles`
+#[allow(dead_code)]
+pub fn solve_batch(puzzles: Vec<PuzzleDef>) -> Vec<BatchResult> {
+    let workers = thread::available_parallelism()
+        .map_or(1, |workers| workers.get())
+        .min(puzzles.len());
+    // each thread takes the next unsolved puzzle, so a slow puzzle doesn't hold up the others
+    let next = AtomicUsize::new(0);
+    let (next, puzzles) = (&next, &puzzles);
+    let mut results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut results = vec![];
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let puzzle = match puzzles.get(index) {
+                            Some(puzzle) => puzzle,
+                            None => break,
+                        };
+                        let start = Instant::now();
+                        let result = puzzle.solver().solve();
+                        results.push((index, result, start.elapsed()));
+                    }
+                    results
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("A solver thread panicked"))
+            .collect()
+    });
+    results.sort_by_key(|(index, _, _)| *index);
+    results
+}
+
+fn parse_token_type(s: &str) -> Option<TokenType> {
+    match s {
+        "Laser" => Some(TokenType::Laser),
+        "TargetMirror" => Some(TokenType::TargetMirror),
+        "BeamSplitter" => Some(TokenType::BeamSplitter),
+        "DoubleMirror" => Some(TokenType::DoubleMirror),
+        "Checkpoint" => Some(TokenType::Checkpoint),
+        "CellBlocker" => Some(TokenType::CellBlocker),
+        _ => None,
+    }
+}
+
+fn parse_orientation(s: &str) -> Option<Orientation> {
+    match s {
+        "North" => Some(Orientation::North),
+        "East" => Some(Orientation::East),
+        "South" => Some(Orientation::South),
+        "West" => Some(Orientation::West),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Bonus Challenge 2, see test_solver_puzzle_62
+    const BONUS_CHALLENGE_2: &str = "
+        # challenge, targets, placement, cell, type, orientation, must_light
+        62, 2, grid, 0, TargetMirror, , false
+        62, 2, grid, 11, Laser, ,
+        62, 2, grid, 14, DoubleMirror, , false
+        62, 2, grid, 17, Checkpoint, East, false
+        62, 2, grid, 22, TargetMirror, , false
+        62, 2, add, , TargetMirror, , false
+        62, 2, add, , TargetMirror, , false
+        62, 2, add, , TargetMirror, , false
+        62, 2, add, , BeamSplitter, , false
+        62, 2, add, , BeamSplitter, , false
+    ";
+
+    #[test]
+    fn test_set_cell() {
+        let mut puzzle_def = PuzzleDef {
+            id: 0,
+            grid: Default::default(),
+            to_be_added: vec![],
+            targets: 1,
+        };
+        // A1 is top left, E1 is bottom left, A5 is top right
+        puzzle_def.set_cell(0, 0, Some(Token::laser(None)));
+        puzzle_def.set_cell(4, 0, Some(Token::target(None, false)));
+        puzzle_def.set_cell(0, 4, Some(Token::double(None)));
+        assert_eq!(puzzle_def.grid[20], Some(Token::laser(None)));
+        assert_eq!(puzzle_def.grid[0], Some(Token::target(None, false)));
+        assert_eq!(puzzle_def.grid[24], Some(Token::double(None)));
+
+        puzzle_def.set_cell(0, 0, None);
+        assert_eq!(puzzle_def.grid[20], None);
+    }
+
+    #[test]
+    fn test_solution_matches_puzzle() {
+        let puzzle_def = &parse_challenge_table(BONUS_CHALLENGE_2).unwrap()[0];
+        let solution = puzzle_def.clone().solver().solve().unwrap().unwrap();
+        assert!(solution_matches_puzzle(puzzle_def, &solution));
+
+        // a fixed token was moved
+        let mut moved = solution.clone();
+        moved[1] = moved[0].take();
+        assert!(!solution_matches_puzzle(puzzle_def, &moved));
+
+        // the checkpoint's orientation is given on the card
+        let mut turned = solution.clone();
+        turned[17] = Some(Token::checkpoint(Some(Orientation::West)));
+        assert!(!solution_matches_puzzle(puzzle_def, &turned));
+
+        // a token was left unoriented
+        let mut unoriented = solution.clone();
+        unoriented[13].as_mut().unwrap().orientation = None;
+        assert!(!solution_matches_puzzle(puzzle_def, &unoriented));
+
+        // one token too many, and one too few
+        let mut extra = solution.clone();
+        extra[1] = Some(Token::target(Some(Orientation::North), false));
+        assert!(!solution_matches_puzzle(puzzle_def, &extra));
+        let mut missing = solution.clone();
+        missing[13] = None;
+        assert!(!solution_matches_puzzle(puzzle_def, &missing));
+
+        // a beam splitter swapped for a target mirror
+        let mut swapped = solution;
+        swapped[13] = Some(Token::target(Some(Orientation::North), false));
+        assert!(!solution_matches_puzzle(puzzle_def, &swapped));
+    }
+
+    #[test]
+    fn test_solution_matches_puzzle_oriented_to_be_added() {
+        // the oriented splitter to be added has to be the one facing east, even though the
+        // unoriented one is listed first
+        let puzzle_def = PuzzleDef {
+            id: 0,
+            grid: Default::default(),
+            to_be_added: vec![
+                Token::splitter(None),
+                Token::splitter(Some(Orientation::East)),
+            ],
+            targets: 1,
+        };
+        let mut solution: [Option<Token>; 25] = Default::default();
+        solution[0] = Some(Token::splitter(Some(Orientation::East)));
+        solution[1] = Some(Token::splitter(Some(Orientation::North)));
+        assert!(solution_matches_puzzle(&puzzle_def, &solution));
+
+        solution[0] = Some(Token::splitter(Some(Orientation::North)));
+        assert!(!solution_matches_puzzle(&puzzle_def, &solution));
+    }
+
+    #[test]
+    fn test_solve_batch() {
+        let mut puzzles = parse_challenge_table(BONUS_CHALLENGE_2).unwrap();
+        // no laser anywhere
+        puzzles.push(PuzzleDef {
+            id: 1,
+            grid: Default::default(),
+            to_be_added: vec![Token::target(None, false)],
+            targets: 1,
+        });
+        // the laser points off the board, and there's nothing to turn it
+        let mut unsolvable = PuzzleDef {
+            id: 2,
+            grid: Default::default(),
+            to_be_added: vec![],
+            targets: 1,
+        };
+        unsolvable.set_cell(0, 0, Some(Token::laser(Some(Orientation::North))));
+        unsolvable.set_cell(4, 4, Some(Token::target(Some(Orientation::North), false)));
+        puzzles.push(unsolvable);
+
+        let results = solve_batch(puzzles.clone());
+        assert_eq!(
+            results
+                .iter()
+                .map(|(index, _, _)| *index)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        let solution = results[0].1.as_ref().unwrap().as_ref().unwrap();
+        assert!(solution_matches_puzzle(&puzzles[0], solution));
+        assert!(results[1].1.is_err());
+        assert_eq!(results[2].1, Ok(None));
+
+        assert!(solve_batch(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_challenge_table() {
+        let puzzle_defs = parse_challenge_table(BONUS_CHALLENGE_2).unwrap();
+        assert_eq!(puzzle_defs.len(), 1);
+
+        let puzzle_def = &puzzle_defs[0];
+        assert_eq!(puzzle_def.id, 62);
+        assert_eq!(puzzle_def.targets, 2);
+        assert_eq!(puzzle_def.grid.iter().flatten().count(), 5);
+        assert_eq!(puzzle_def.grid[11], Some(Token::laser(None)));
+        assert_eq!(
+            puzzle_def.grid[17],
+            Some(Token::checkpoint(Some(Orientation::East)))
+        );
+        assert_eq!(puzzle_def.to_be_added.len(), 5);
+
+        let solution = puzzle_def.solver().solve().unwrap();
+        assert!(solution.is_some());
+    }
+
+    #[test]
+    fn test_parse_challenge_table_tabs_and_multiple_challenges() {
+        let puzzle_defs =
+            parse_challenge_table("1\t1\tgrid\t0\tLaser\tNorth\t\n2\t3\tadd\t\tLaser\t\t\n")
+                .unwrap();
+        assert_eq!(
+            puzzle_defs.iter().map(|p| p.id).collect::<Vec<u32>>(),
+            vec![1, 2]
+        );
+        assert_eq!(puzzle_defs[1].to_be_added, vec![Token::laser(None)]);
+    }
+
+    #[test]
+    fn test_parse_challenge_table_errors() {
+        assert_eq!(
+            parse_challenge_table("1, 1, grid, 25, Laser, North, false").unwrap_err(),
+            "Line 1: invalid cell index!"
+        );
+        assert_eq!(
+            parse_challenge_table("1, 1, grid, 0, Lazer, North, false").unwrap_err(),
+            "Line 1: invalid token type!"
+        );
+        assert_eq!(
+            parse_challenge_table("1, 1, grid, 0, Laser, Up, false").unwrap_err(),
+            "Line 1: invalid orientation!"
+        );
+        assert_eq!(
+            parse_challenge_table("1, 1, grid, 0, Laser, North, true").unwrap_err(),
+            "Line 1: only a TargetMirror may be must_light!"
+        );
+        assert_eq!(
+            parse_challenge_table("1, 1, add, 3, Laser, , false").unwrap_err(),
+            "Line 1: tokens to be added can't have a cell index!"
+        );
+        assert_eq!(
+            parse_challenge_table("1, 1, grid, 0, Laser, North\n").unwrap_err(),
+            "Line 1: expected 7 columns, found 6!"
+        );
+        assert_eq!(
+            parse_challenge_table(
+                "1, 1, grid, 0, Laser, North, false\n1, 1, grid, 0, TargetMirror, , true"
+            )
+            .unwrap_err(),
+            "Line 2: cell 0 is already occupied!"
+        );
+        assert_eq!(
+            parse_challenge_table(
+                "1, 1, grid, 0, Laser, North, false\n1, 2, grid, 1, TargetMirror, , true"
+            )
+            .unwrap_err(),
+            "Line 2: number of targets disagrees with earlier rows of challenge 1!"
+        );
+    }
+}
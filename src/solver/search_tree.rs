@@ -0,0 +1,101 @@
+use crate::solver::board::Board;
+use crate::solver::token::Token;
+use serde::Serialize;
+
+/// SearchTreeNode: a node the solver searched, and the node it was branched from
+/// parent: the index in SearchTree::nodes of the node this one branched from; None for the first node
+/// cells: the board, as it was when the solver took the node off the stack
+/// tokens_to_be_added: the tokens still to be placed on the board
+/// solution: whether the board is the solution the solver returned
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SearchTreeNode {
+    pub parent: Option<usize>,
+    pub cells: [Option<Token>; 25],
+    pub tokens_to_be_added: Vec<Token>,
+    pub solution: bool,
+}
+
+/// SearchTree: every node LaserMazeSolver::solve_trace searched, in the order they were searched.
+/// Serialize it with serde_json, or draw it with Graphviz from .to_dot()
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SearchTree {
+    pub nodes: Vec<SearchTreeNode>,
+}
+
+#[allow(dead_code)]
+impl SearchTree {
+    // adds a node, returning its index
+    pub fn push(
+        &mut self,
+        parent: Option<usize>,
+        cells: [Option<Token>; 25],
+        tokens_to_be_added: Vec<Token>,
+    ) -> usize {
+        self.nodes.push(SearchTreeNode {
+            parent,
+            cells,
+            tokens_to_be_added,
+            solution: false,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// The nodes branched from the node at `index`
+    pub fn children(&self, index: usize) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|i| self.nodes[*i].parent == Some(index))
+            .collect()
+    }
+
+    /// The tree in Graphviz's DOT language, with each node labelled by its board (see
+    /// Board::render_ascii) and how many tokens are left to be added. The solution is filled in
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![String::from("digraph search_tree {")];
+        lines.push(String::from("    node [shape=box, fontname=monospace];"));
+        for (i, node) in self.nodes.iter().enumerate() {
+            // \l ends a left-aligned line
+            let board = Board::new(node.cells.clone())
+                .render_ascii()
+                .replace('\n', "\\l");
+            let style = if node.solution { ", style=filled" } else { "" };
+            lines.push(format!(
+                "    {} [label=\"{}\\l{} to be added\\l\"{}];",
+                i,
+                board,
+                node.tokens_to_be_added.len(),
+                style
+            ));
+            if let Some(parent) = node.parent {
+                lines.push(format!("    {} -> {};", parent, i));
+            }
+        }
+        lines.push(String::from("}"));
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solver::orientation::Orientation;
+
+    #[test]
+    fn test_to_dot() {
+        let mut tree = SearchTree::default();
+        let mut cells: [Option<Token>; 25] = Default::default();
+        let root = tree.push(None, cells.clone(), vec![Token::laser(None)]);
+        cells[0] = Some(Token::laser(Some(Orientation::North)));
+        let child = tree.push(Some(root), cells, vec![]);
+        tree.nodes[child].solution = true;
+
+        assert_eq!(tree.children(root), vec![child]);
+        assert!(tree.children(child).is_empty());
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph search_tree {"));
+        assert!(dot.contains("    0 [label=\".. .. .. .. ..\\l"));
+        assert!(dot.contains("L^ .. .. .. ..\\l0 to be added\\l\", style=filled];"));
+        assert!(dot.contains("    0 -> 1;"));
+        assert!(dot.ends_with('}'));
+    }
+}
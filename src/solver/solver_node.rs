@@ -1,8 +1,10 @@
-use crate::solver::checker::Checker;
+use crate::solver::checker::{CheckError, Checker};
 use crate::solver::orientation::Orientation;
-use crate::solver::token::{Token, TokenType};
+use crate::solver::token::{MirrorRules, Token, TokenType};
+use crate::solver::{is_fully_oriented, laser_state};
 pub mod active_laser;
 use lazy_static::lazy_static;
+use std::cmp::Reverse;
 
 #[derive(Clone, Default, Debug)]
 pub struct SolverNode {
@@ -10,6 +12,12 @@ pub struct SolverNode {
     pub tokens_to_be_added: Vec<Token>,
     pub tokens_to_be_added_shuffled: Vec<Token>,
     pub targets: u8,
+    // cells the laser must pass through for the puzzle to be solved
+    pub waypoints: Vec<usize>,
+    // cells the laser must not enter for the puzzle to be solved
+    pub forbidden_cells: Vec<usize>,
+    pub mirror_rules: MirrorRules,
+    pub placement_order: PlacementOrder,
 }
 
 impl SolverNode {
@@ -27,7 +35,7 @@ impl SolverNode {
 
         // now, make a checker. it will march the laser forward.
         // it will return Ok() if we hit the solution, or Err(new_nodes) otherwise
-        self.clone_to_checker().check().generate_branches()
+        self.clone_to_checker().generate_branches()
     }
 
     fn generate_laser_placement_branches(&mut self) -> Vec<Self> {
@@ -42,25 +50,62 @@ impl SolverNode {
             )
         } else {
             // the laser hasn't been placed or rotated
-            // get the laser out of tokens_to_be_added
+            // get the laser out of tokens_to_be_added. LaserMazeSolver::validate allows exactly one laser,
+            // so only one is placed below
+            debug_assert_eq!(
+                self.tokens_to_be_added
+                    .iter()
+                    .filter(|token| token.type_() == &TokenType::Laser)
+                    .count(),
+                1,
+                "Expected exactly one laser to be added"
+            );
             self.tokens_to_be_added
                 .retain(|token| token.type_() != &TokenType::Laser);
             let laser = Token::new(TokenType::Laser, None, false);
             let mut result = vec![];
-            for i in SPIRAL_ORDER_REVERSE.iter() {
-                // find all unoccupied cells
-                if self.cells[*i].is_none() {
-                    // make a copy of this node, place the laser token in this unoccupied slot, and make new nodes for all the orientations of the laser
-                    let mut new_node = self.clone();
-                    new_node.cells[*i] = Some(laser.clone());
-                    let new_nodes = new_node.generate_orientation_branches_at_cell(*i);
-                    result.extend(new_nodes);
-                }
+            for i in self.laser_placement_order() {
+                // make a copy of this node, place the laser token in this unoccupied slot, and make new nodes for all the orientations of the laser
+                let mut new_node = self.clone();
+                new_node.cells[i] = Some(laser.clone());
+                let new_nodes = new_node.generate_orientation_branches_at_cell(i);
+                result.extend(new_nodes);
             }
             result
         }
     }
 
+    // every unoccupied cell, in the order to place the laser in. cells in line (the same row or
+    // column) with more must-light targets come first, ties keeping the placement order, and as
+    // the branches are pushed onto the stack, the last cell is searched first. measured with the
+    // spiral placement order, this searches ~40% fewer nodes than the plain spiral order on puzzle
+    // 54 and ~35% fewer on puzzle 153, whereas searching the cells nearest the must-light targets
+    // first searched more nodes on every puzzle with the laser in the tray
+    fn laser_placement_order(&self) -> Vec<usize> {
+        let must_light: Vec<usize> = (0..25)
+            .filter(|i| {
+                self.cells[*i]
+                    .as_ref()
+                    .is_some_and(|token| token.must_light())
+            })
+            .collect();
+        let mut order: Vec<usize> = self
+            .placement_order
+            .push_order()
+            .into_iter()
+            .filter(|i| self.cells[*i].is_none())
+            .collect();
+        order.sort_by_key(|i| {
+            Reverse(
+                must_light
+                    .iter()
+                    .filter(|target| cells_in_line(*i, **target))
+                    .count(),
+            )
+        });
+        order
+    }
+
     pub fn generate_orientation_branches_at_cell(&self, cell_index: usize) -> Vec<Self> {
         if let Some(token) = self.cells[cell_index].as_ref() {
             let mut result = vec![];
@@ -105,52 +150,20 @@ impl SolverNode {
     }
 
     fn laser_position(&self) -> Option<usize> {
-        self.cells.as_ref().iter().position(|token| {
-            if let Some(token) = token {
-                token.type_() == &TokenType::Laser
-            } else {
-                false
-            }
-        })
+        laser_state(&self.cells).map(|(cell_index, _)| cell_index)
     }
 
     fn laser_placed(&self) -> bool {
-        self.cells
-            .as_ref()
-            .iter()
-            .flatten()
-            .any(|token| token.type_() == &TokenType::Laser)
+        laser_state(&self.cells).is_some()
     }
 
     fn laser_placed_and_rotated(&self) -> bool {
-        self.cells
-            .as_ref()
-            .iter()
-            .flatten()
-            .any(|token| token.type_() == &TokenType::Laser && token.orientation().is_some())
+        matches!(laser_state(&self.cells), Some((_, Some(_))))
     }
 
     #[allow(dead_code)]
     pub fn all_placed_tokens_have_orientation_set(&self) -> bool {
-        self.cells
-            .as_ref()
-            .iter()
-            .flatten()
-            .all(|token| token.orientation().is_some())
-    }
-
-    fn count_tokens_to_be_added_by_type(&self, type_: TokenType) -> usize {
-        self.tokens_to_be_added
-            .iter()
-            .filter(|token| token.type_() == &type_)
-            .count()
-    }
-
-    fn count_must_light_tokens_to_be_added(&self) -> usize {
-        self.tokens_to_be_added
-            .iter()
-            .filter(|token| token.must_light())
-            .count()
+        is_fully_oriented(&self.cells)
     }
 
     fn generate_shuffled_tokens_to_be_added_branches(&mut self) -> Vec<Self> {
@@ -158,118 +171,61 @@ impl SolverNode {
         // we may only have TargetMirrors, Checkpoints, DoubleMirrors, and BeamSplitters. Given that we have t, c, d, and b of each,
         // we will generate this many shufflings: (t+c+d+b)!/(t!c!d!b!). In the worst case, we'll have t=5, c=1, d=1, b=2, generating
         // (5+1+1+2)!/(5!2!) = 1512 shufflings.
+        // a token to be added may already be oriented; it keeps its orientation, so it's only interchangeable
+        // with identical tokens
 
-        // recursively build a list of the unique permutations
+        // recursively build a list of the unique permutations. `groups` holds each distinct token, and how
+        // many of it are left to place
         fn backtrack(
-            n_target_mirrors_must_light: usize,
-            n_target_mirrors_may_not_light: usize,
-            n_checkpoints: usize,
-            n_double_mirrors: usize,
-            n_beam_splitters: usize,
-            current_ordering: Vec<Token>,
+            groups: &mut Vec<(Token, usize)>,
+            current_ordering: &mut Vec<Token>,
             unique_orderings: &mut Vec<Vec<Token>>,
         ) {
-            if n_target_mirrors_must_light == 0
-                && n_target_mirrors_may_not_light == 0
-                && n_checkpoints == 0
-                && n_double_mirrors == 0
-                && n_beam_splitters == 0
-            {
-                unique_orderings.push(current_ordering);
+            if groups.iter().all(|(_, count)| *count == 0) {
+                unique_orderings.push(current_ordering.clone());
                 return;
             }
 
-            if n_target_mirrors_must_light > 0 {
-                let mut new_ordering = current_ordering.clone();
-                new_ordering.push(Token::new(TokenType::TargetMirror, None, true));
-                backtrack(
-                    n_target_mirrors_must_light - 1,
-                    n_target_mirrors_may_not_light,
-                    n_checkpoints,
-                    n_double_mirrors,
-                    n_beam_splitters,
-                    new_ordering,
-                    unique_orderings,
-                );
-            }
-
-            if n_target_mirrors_may_not_light > 0 {
-                let mut new_ordering = current_ordering.clone();
-                new_ordering.push(Token::new(TokenType::TargetMirror, None, false));
-                backtrack(
-                    n_target_mirrors_must_light,
-                    n_target_mirrors_may_not_light - 1,
-                    n_checkpoints,
-                    n_double_mirrors,
-                    n_beam_splitters,
-                    new_ordering,
-                    unique_orderings,
-                );
-            }
-
-            if n_checkpoints > 0 {
-                let mut new_ordering = current_ordering.clone();
-                new_ordering.push(Token::new(TokenType::Checkpoint, None, false));
-                backtrack(
-                    n_target_mirrors_must_light,
-                    n_target_mirrors_may_not_light,
-                    n_checkpoints - 1,
-                    n_double_mirrors,
-                    n_beam_splitters,
-                    new_ordering,
-                    unique_orderings,
-                );
-            }
-
-            if n_double_mirrors > 0 {
-                let mut new_ordering = current_ordering.clone();
-                new_ordering.push(Token::new(TokenType::DoubleMirror, None, false));
-                backtrack(
-                    n_target_mirrors_must_light,
-                    n_target_mirrors_may_not_light,
-                    n_checkpoints,
-                    n_double_mirrors - 1,
-                    n_beam_splitters,
-                    new_ordering,
-                    unique_orderings,
-                );
+            for i in 0..groups.len() {
+                if groups[i].1 > 0 {
+                    groups[i].1 -= 1;
+                    current_ordering.push(groups[i].0.clone());
+                    backtrack(groups, current_ordering, unique_orderings);
+                    current_ordering.pop();
+                    groups[i].1 += 1;
+                }
             }
+        }
 
-            if n_beam_splitters > 0 {
-                let mut new_ordering = current_ordering;
-                new_ordering.push(Token::new(TokenType::BeamSplitter, None, false));
-                backtrack(
-                    n_target_mirrors_must_light,
-                    n_target_mirrors_may_not_light,
-                    n_checkpoints,
-                    n_double_mirrors,
-                    n_beam_splitters - 1,
-                    new_ordering,
-                    unique_orderings,
-                );
+        // unoriented tokens first, in the order they've always been tried: target mirrors which must
+        // be lit, other target mirrors, checkpoints, double mirrors, then beam splitters
+        let mut tokens_to_be_added = self.tokens_to_be_added.clone();
+        tokens_to_be_added.iter_mut().for_each(Token::reset);
+        tokens_to_be_added.sort_by_key(|token| {
+            (
+                token
+                    .orientation()
+                    .map(|orientation| orientation.to_index()),
+                match token.type_() {
+                    TokenType::TargetMirror if token.must_light() => 0,
+                    TokenType::TargetMirror => 1,
+                    TokenType::Checkpoint => 2,
+                    TokenType::DoubleMirror => 3,
+                    _ => 4,
+                },
+                *token.type_(),
+            )
+        });
+        let mut groups: Vec<(Token, usize)> = vec![];
+        for token in tokens_to_be_added {
+            match groups.last_mut() {
+                Some((group_token, count)) if *group_token == token => *count += 1,
+                _ => groups.push((token, 1)),
             }
         }
 
-        let n_target_mirrors_must_light = self.count_must_light_tokens_to_be_added();
-        let n_target_mirrors_may_not_light = self
-            .count_tokens_to_be_added_by_type(TokenType::TargetMirror)
-            - n_target_mirrors_must_light;
-        let n_checkpoints = self.count_tokens_to_be_added_by_type(TokenType::Checkpoint);
-        let n_double_mirrors = self.count_tokens_to_be_added_by_type(TokenType::DoubleMirror);
-        let n_beam_splitters = self.count_tokens_to_be_added_by_type(TokenType::BeamSplitter);
-
         let mut unique_orderings: Vec<Vec<Token>> = vec![];
-        let current_ordering: Vec<Token> = vec![];
-
-        backtrack(
-            n_target_mirrors_must_light,
-            n_target_mirrors_may_not_light,
-            n_checkpoints,
-            n_double_mirrors,
-            n_beam_splitters,
-            current_ordering,
-            &mut unique_orderings,
-        );
+        backtrack(&mut groups, &mut vec![], &mut unique_orderings);
 
         let mut result = vec![];
 
@@ -284,7 +240,7 @@ impl SolverNode {
     }
 
     // for generating rotation branches, which rotations are valid?
-    fn orientation_iter(&self, token_type: &TokenType, cell_index: usize) -> Vec<usize> {
+    pub fn orientation_iter(&self, token_type: &TokenType, cell_index: usize) -> Vec<usize> {
         let mut result = token_type.orientation_range();
 
         // if the token can point out of the board, directly return this token type's orientation range
@@ -305,6 +261,12 @@ impl SolverNode {
             .map(|o| o.to_index())
             .collect::<Vec<usize>>();
 
+        // the laser and checkpoint both emit a beam, so they also can't point into a side of a
+        // neighboring token which rejects the laser
+        if [TokenType::Laser, TokenType::Checkpoint].contains(token_type) {
+            forbidden_directions.extend(self.dead_end_orientations(cell_index));
+        }
+
         match token_type {
             // the laser has no symmetry so we can directly use forbidden_directions to prune the result
             TokenType::Laser => {
@@ -333,6 +295,29 @@ impl SolverNode {
         }
     }
 
+    // whether the token at `cell_index` may face `orientation` without pointing off the board where
+    // the rules forbid it. beam splitters, double mirrors, and targets which needn't be lit may point
+    // out; an empty cell has nothing to orient
+    pub fn orientation_is_legal(&self, cell_index: usize, orientation: &Orientation) -> bool {
+        let token = match &self.cells[cell_index] {
+            Some(token) => token,
+            None => return true,
+        };
+        let forbidden_directions = self.forbidden_orientations(cell_index);
+        let mut forbidden_directions = forbidden_directions.iter().flatten();
+        match token.type_() {
+            TokenType::Laser => !forbidden_directions.any(|o| o == orientation),
+            TokenType::TargetMirror if token.must_light() => {
+                !forbidden_directions.any(|o| o == orientation)
+            }
+            // the checkpoint has 180 degree symmetry, so neither end may point out
+            TokenType::Checkpoint => {
+                !forbidden_directions.any(|o| o == orientation || *o == orientation.opposite())
+            }
+            _ => true,
+        }
+    }
+
     fn target_mirror_orientation_iter(
         &self,
         forbidden_directions: Vec<usize>,
@@ -347,13 +332,84 @@ impl SolverNode {
                 )
             }
             if target_mirror_token.must_light() {
-                result.retain(|orientation_idx| !forbidden_directions.contains(orientation_idx));
+                // nor can it face a side of a neighboring token which rejects the laser, since that
+                // token never sends a beam back this way to light the target
+                let dead_ends = self.dead_end_orientations(cell_index);
+                result.retain(|orientation_idx| {
+                    !forbidden_directions.contains(orientation_idx)
+                        && !dead_ends.contains(orientation_idx)
+                });
                 return result;
             }
         } else {
             panic!("Tried checking target mirror rotations on a cell not holding a target mirror")
         }
 
+        // a target which needn't be lit may face out, e.g. to use its mirror side, unless every target
+        // which could still be lit is needed to light the puzzle's number of targets
+        if !forbidden_directions.is_empty()
+            && self.count_lightable_targets() <= self.targets as usize
+        {
+            result.retain(|orientation_idx| !forbidden_directions.contains(orientation_idx));
+        }
+
+        result
+    }
+
+    // the target mirrors on the board, or still to be placed, whose target could still be lit; an
+    // oriented target facing out can't be
+    fn count_lightable_targets(&self) -> usize {
+        let on_board = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(cell_index, cell)| match cell {
+                Some(token) if token.type_() == &TokenType::TargetMirror => {
+                    match token.orientation() {
+                        Some(orientation) => !self
+                            .forbidden_orientations(*cell_index)
+                            .contains(&Some(orientation.clone())),
+                        None => true,
+                    }
+                }
+                _ => false,
+            })
+            .count();
+        let to_be_placed = self
+            .tokens_to_be_added
+            .iter()
+            .chain(self.tokens_to_be_added_shuffled.iter())
+            .filter(|token| token.type_() == &TokenType::TargetMirror)
+            .count();
+        on_board + to_be_placed
+    }
+
+    // returns the orientations in which a beam leaving `cell_index` is guaranteed to fail the puzzle,
+    // because it hits a side of an oriented token which can't accept a laser (e.g. the back of a target
+    // mirror). cell blockers are transparent to the laser, so we look past them. an empty cell or an
+    // unoriented token could still change the beam's path, so we can't rule anything out there.
+    // a token which rejects a laser never emits a beam back the other way, so a beam can't enter this
+    // cell from that side either
+    fn dead_end_orientations(&self, cell_index: usize) -> Vec<usize> {
+        let mut result = vec![];
+        for orientation_index in 0..4 {
+            let orientation = Orientation::from_index(orientation_index);
+            let mut next_cell_index = neighbors(cell_index)[orientation_index];
+            while let Some(i) = next_cell_index {
+                match &self.cells[i] {
+                    Some(token) if token.type_() == &TokenType::CellBlocker => {
+                        next_cell_index = neighbors(i)[orientation_index];
+                    }
+                    Some(token) if token.orientation().is_some() => {
+                        if token.rejects_inbound_laser(&orientation) {
+                            result.push(orientation_index);
+                        }
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+        }
         result
     }
 
@@ -366,106 +422,35 @@ impl SolverNode {
 
         // we need to check the cell blocker first because edge pieces can have a different result from this
         // function if the cell blocker is on a corner
-        if let Some((cell_blocker_index, _)) =
-            self.cells.as_ref().iter().enumerate().find(|(_, token)| {
-                if let Some(token) = token {
-                    token.type_() == &TokenType::CellBlocker
-                } else {
-                    false
-                }
-            })
-        {
-            // neighboring_cell_indices are the cell(s) neighboring the blocker we need to check
-            let neighboring_cell_indices = match cell_blocker_index {
-                // corners
-                0 => [Some(1), Some(5)],
-                4 => [Some(3), Some(9)],
-                20 => [Some(15), Some(21)],
-                24 => [Some(23), Some(19)],
-                // edges, but not a corner
-                1 => [Some(6), None],
-                2 => [Some(7), None],
-                3 => [Some(8), None],
-                9 => [Some(8), None],
-                14 => [Some(13), None],
-                19 => [Some(18), None],
-                23 => [Some(18), None],
-                22 => [Some(17), None],
-                21 => [Some(16), None],
-                15 => [Some(16), None],
-                10 => [Some(11), None],
-                5 => [Some(6), None],
-                // cell blocker is not on an edge
-                _ => [None, None],
-            };
-            if neighboring_cell_indices
-                .into_iter()
+        if let Some(cell_blocker_index) = self.cells.as_ref().iter().position(|token| {
+            if let Some(token) = token {
+                token.type_() == &TokenType::CellBlocker
+            } else {
+                false
+            }
+        }) {
+            // a cell blocker on the edge extends that edge to the cell behind it, i.e. the neighbor
+            // opposite each out-of-board direction of the blocker. if the cell blocker is on a corner,
+            // both of its neighbors are impacted, and can't face either of the corner's edges
+            let blocker_out_of_board_orientations = out_of_board_orientations(cell_blocker_index);
+            let blocker_neighbors = neighbors(cell_blocker_index);
+            if blocker_out_of_board_orientations
+                .iter()
                 .flatten()
-                .any(|idx| idx == cell_index)
+                .any(|orientation| {
+                    blocker_neighbors[orientation.opposite().to_index()] == Some(cell_index)
+                })
             {
-                // now, we know that the token is impacted by the cell blocker.
-                // if the cell blocker is on a non-corner edge, it's unambiguous which direction the laser cannot face
-                if NORTH_EDGE_CELL_INDICES.contains(&cell_blocker_index) {
-                    return [Some(Orientation::North), None];
-                }
-                if EAST_EDGE_CELL_INDICES.contains(&cell_blocker_index) {
-                    return [Some(Orientation::East), None];
-                }
-                if SOUTH_EDGE_CELL_INDICES.contains(&cell_blocker_index) {
-                    return [Some(Orientation::South), None];
-                }
-                if WEST_EDGE_CELL_INDICES.contains(&cell_blocker_index) {
-                    return [Some(Orientation::West), None];
-                }
-                // if we reach this point, the cell blocker is on a corner, AND the piece is on an edge neighboring that corner
-                match cell_index {
-                    1 => return [Some(Orientation::South), Some(Orientation::West)],
-                    3 => return [Some(Orientation::South), Some(Orientation::East)],
-                    9 => return [Some(Orientation::South), Some(Orientation::East)],
-                    19 => return [Some(Orientation::North), Some(Orientation::East)],
-                    23 => return [Some(Orientation::North), Some(Orientation::East)],
-                    21 => return [Some(Orientation::North), Some(Orientation::West)],
-                    15 => return [Some(Orientation::North), Some(Orientation::West)],
-                    5 => return [Some(Orientation::South), Some(Orientation::West)],
-                    _ => panic!("Logical error in is_edge_cell()"),
-                }
+                return blocker_out_of_board_orientations;
             }
         }
 
-        // now we know the cell blocker is not on the edge
-
-        // corners
-        if cell_index == 0 {
-            return [Some(Orientation::South), Some(Orientation::West)];
-        }
-        if cell_index == 4 {
-            return [Some(Orientation::South), Some(Orientation::East)];
-        }
-        if cell_index == 20 {
-            return [Some(Orientation::North), Some(Orientation::West)];
-        }
-        if cell_index == 24 {
-            return [Some(Orientation::North), Some(Orientation::East)];
-        }
-        // edges, but not on corner
-        if NORTH_EDGE_CELL_INDICES.contains(&cell_index) {
-            return [Some(Orientation::North), None];
-        }
-        if EAST_EDGE_CELL_INDICES.contains(&cell_index) {
-            return [Some(Orientation::East), None];
-        }
-        if SOUTH_EDGE_CELL_INDICES.contains(&cell_index) {
-            return [Some(Orientation::South), None];
-        }
-        if WEST_EDGE_CELL_INDICES.contains(&cell_index) {
-            return [Some(Orientation::West), None];
-        }
-
-        [None, None]
+        // now we know the cell blocker doesn't impact this cell
+        out_of_board_orientations(cell_index)
     }
 
     #[allow(dead_code)]
-    pub fn check(self) -> Checker {
+    pub fn check(self) -> Result<Checker, CheckError> {
         let checker = self.clone_to_checker();
         checker.check()
     }
@@ -489,6 +474,81 @@ impl SolverNode {
     }
 }
 
+// returns the indices of the cells neighboring `cell_index` in N/E/S/W order, matching
+// Orientation::to_index(). a neighbor is None if it would be off the board
+pub fn neighbors(cell_index: usize) -> [Option<usize>; 4] {
+    let row = cell_index / 5;
+    let col = cell_index % 5;
+    [
+        if row < 4 { Some(cell_index + 5) } else { None },
+        if col < 4 { Some(cell_index + 1) } else { None },
+        if row > 0 { Some(cell_index - 5) } else { None },
+        if col > 0 { Some(cell_index - 1) } else { None },
+    ]
+}
+
+// whether two cells are in the same row or column
+fn cells_in_line(a: usize, b: usize) -> bool {
+    a / 5 == b / 5 || a % 5 == b % 5
+}
+
+/// CellRegion: where a cell is on the board, with the orientations which point off the board from it.
+/// A corner's two orientations are in N/E/S/W order
+#[derive(Clone, Debug, PartialEq)]
+pub enum CellRegion {
+    Corner(Orientation, Orientation),
+    Edge(Orientation),
+    Interior,
+}
+
+/// The region of the board `cell_index` (0 is bottom left) is in
+pub fn cell_region(cell_index: usize) -> CellRegion {
+    let mut orientations = neighbors(cell_index)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, neighbor)| neighbor.is_none())
+        .map(|(orientation_index, _)| Orientation::from_index(orientation_index));
+    match (orientations.next(), orientations.next()) {
+        (Some(first), Some(second)) => CellRegion::Corner(first, second),
+        (Some(orientation), None) => CellRegion::Edge(orientation),
+        _ => CellRegion::Interior,
+    }
+}
+
+// returns the (up to 2) orientations which point off the board from `cell_index`
+fn out_of_board_orientations(cell_index: usize) -> [Option<Orientation>; 2] {
+    match cell_region(cell_index) {
+        CellRegion::Corner(first, second) => [Some(first), Some(second)],
+        CellRegion::Edge(orientation) => [Some(orientation), None],
+        CellRegion::Interior => [None, None],
+    }
+}
+
+/// PlacementOrder: the order the solver tries the empty cells in, when placing the laser and the
+/// tokens to be added. Which finds a solution soonest depends on the puzzle
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PlacementOrder {
+    // from the bottom left corner, around the edge and in to the center
+    #[default]
+    Spiral,
+    // the bottom row first, left to right, then each row above it
+    RowMajor,
+    // the spiral backwards, from the center out to the bottom left corner
+    CenterOut,
+}
+
+impl PlacementOrder {
+    // the cells in the order their branches are pushed onto the stack, so the last cell is searched
+    // first
+    pub fn push_order(&self) -> [usize; 25] {
+        match self {
+            PlacementOrder::Spiral => core::array::from_fn(|i| SPIRAL_ORDER_REVERSE[i]),
+            PlacementOrder::RowMajor => core::array::from_fn(|i| 24 - i),
+            PlacementOrder::CenterOut => core::array::from_fn(|i| SPIRAL_ORDER[i]),
+        }
+    }
+}
+
 lazy_static! {
     pub static ref SPIRAL_ORDER: [usize; 25] = [
         0, 1, 2, 3, 4, 9, 14, 19, 24, 23, 22, 21, 20, 15, 10, 5, 6, 7, 8, 13, 18, 17, 16, 11, 12,
@@ -501,23 +561,307 @@ lazy_static! {
     ];
 }
 
-lazy_static! {
-    static ref EDGE_CELL_INDICES: [usize; 16] =
-        [0, 1, 2, 3, 4, 9, 14, 19, 24, 23, 22, 21, 20, 15, 10, 5,];
-}
+#[cfg(test)]
+mod test {
+    use super::*;
 
-lazy_static! {
-    static ref NORTH_EDGE_CELL_INDICES: [usize; 3] = [21, 22, 23,];
-}
+    #[test]
+    fn test_neighbors_corners() {
+        assert_eq!(neighbors(0), [Some(5), Some(1), None, None]);
+        assert_eq!(neighbors(4), [Some(9), None, None, Some(3)]);
+        assert_eq!(neighbors(20), [None, Some(21), Some(15), None]);
+        assert_eq!(neighbors(24), [None, None, Some(19), Some(23)]);
+    }
 
-lazy_static! {
-    static ref EAST_EDGE_CELL_INDICES: [usize; 3] = [9, 14, 19,];
-}
+    #[test]
+    fn test_neighbors_edges() {
+        // south edge
+        for i in [1, 2, 3] {
+            assert_eq!(neighbors(i), [Some(i + 5), Some(i + 1), None, Some(i - 1)]);
+        }
+        // east edge
+        for i in [9, 14, 19] {
+            assert_eq!(neighbors(i), [Some(i + 5), None, Some(i - 5), Some(i - 1)]);
+        }
+        // north edge
+        for i in [21, 22, 23] {
+            assert_eq!(neighbors(i), [None, Some(i + 1), Some(i - 5), Some(i - 1)]);
+        }
+        // west edge
+        for i in [5, 10, 15] {
+            assert_eq!(neighbors(i), [Some(i + 5), Some(i + 1), Some(i - 5), None]);
+        }
+    }
 
-lazy_static! {
-    static ref SOUTH_EDGE_CELL_INDICES: [usize; 3] = [1, 2, 3,];
-}
+    #[test]
+    fn test_neighbors_interior() {
+        for i in [6, 7, 8, 11, 12, 13, 16, 17, 18] {
+            assert_eq!(
+                neighbors(i),
+                [Some(i + 5), Some(i + 1), Some(i - 5), Some(i - 1)]
+            );
+        }
+    }
 
-lazy_static! {
-    static ref WEST_EDGE_CELL_INDICES: [usize; 3] = [5, 10, 15,];
+    #[test]
+    fn test_laser_placement_order() {
+        let mut node = SolverNode::default();
+        node.cells[12] = Some(Token::blocker());
+        // without must-light targets, the empty cells are in the spiral order
+        let spiral: Vec<usize> = SPIRAL_ORDER_REVERSE
+            .iter()
+            .copied()
+            .filter(|i| *i != 12)
+            .collect();
+        assert_eq!(node.laser_placement_order(), spiral);
+
+        // the cells in line with the must-light target in the corner come first, to be searched
+        // last; the rest keep the spiral order
+        node.cells[24] = Some(Token::target(None, true));
+        let order = node.laser_placement_order();
+        assert_eq!(order.len(), 23);
+        assert_eq!(order[..8], [20, 21, 22, 23, 19, 14, 9, 4]);
+        let rest: Vec<usize> = spiral
+            .into_iter()
+            .filter(|i| *i != 24 && !order[..8].contains(i))
+            .collect();
+        assert_eq!(order[8..], rest);
+    }
+
+    #[test]
+    fn test_placement_order() {
+        for placement_order in [
+            PlacementOrder::Spiral,
+            PlacementOrder::RowMajor,
+            PlacementOrder::CenterOut,
+        ] {
+            let mut cells = placement_order.push_order();
+            cells.sort();
+            assert_eq!(cells, core::array::from_fn(|i| i));
+        }
+        // the last cell pushed is searched first
+        assert_eq!(PlacementOrder::Spiral.push_order()[24], 0);
+        assert_eq!(PlacementOrder::RowMajor.push_order()[..3], [24, 23, 22]);
+        assert_eq!(PlacementOrder::CenterOut.push_order()[24], 12);
+
+        // the laser goes in the empty cells in the placement order
+        let mut node = SolverNode {
+            placement_order: PlacementOrder::RowMajor,
+            ..Default::default()
+        };
+        node.cells[12] = Some(Token::blocker());
+        let row_major: Vec<usize> = (0..25).rev().filter(|i| *i != 12).collect();
+        assert_eq!(node.laser_placement_order(), row_major);
+    }
+
+    #[test]
+    fn test_cell_region() {
+        assert_eq!(
+            cell_region(0),
+            CellRegion::Corner(Orientation::South, Orientation::West)
+        );
+        assert_eq!(
+            cell_region(24),
+            CellRegion::Corner(Orientation::North, Orientation::East)
+        );
+        assert_eq!(cell_region(2), CellRegion::Edge(Orientation::South));
+        assert_eq!(cell_region(14), CellRegion::Edge(Orientation::East));
+        assert_eq!(cell_region(22), CellRegion::Edge(Orientation::North));
+        assert_eq!(cell_region(10), CellRegion::Edge(Orientation::West));
+        for i in [6, 7, 8, 11, 12, 13, 16, 17, 18] {
+            assert_eq!(cell_region(i), CellRegion::Interior);
+        }
+    }
+
+    #[test]
+    fn test_laser_cannot_face_back_of_target_mirror() {
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::laser(None));
+        // a target mirror facing south has its back to the west
+        node.cells[1] = Some(Token::target(Some(Orientation::South), false));
+        assert_eq!(node.orientation_iter(&TokenType::Laser, 0), vec![0]);
+
+        // the cell blocker doesn't stop the laser, so the back of the target mirror is still hit
+        node.cells[1] = Some(Token::blocker());
+        node.cells[2] = Some(Token::target(Some(Orientation::South), false));
+        assert_eq!(node.orientation_iter(&TokenType::Laser, 0), vec![0]);
+
+        // an empty cell in between could still be filled with a token that redirects the laser
+        node.cells[1] = None;
+        assert_eq!(node.orientation_iter(&TokenType::Laser, 0), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_preplaced_unoriented_laser_only_branches_inward() {
+        // each corner's laser is only branched on the two orientations pointing into the board
+        for (cell_index, inward) in [
+            (0, [Orientation::North, Orientation::East]),
+            (4, [Orientation::North, Orientation::West]),
+            (20, [Orientation::East, Orientation::South]),
+            (24, [Orientation::South, Orientation::West]),
+        ] {
+            let mut node = SolverNode::default();
+            node.cells[cell_index] = Some(Token::laser(None));
+            let branches = node.generate_branches().unwrap_err();
+            let orientations = branches
+                .iter()
+                .map(|branch| {
+                    branch.cells[cell_index]
+                        .as_ref()
+                        .unwrap()
+                        .orientation()
+                        .cloned()
+                })
+                .collect::<Vec<Option<Orientation>>>();
+            assert_eq!(orientations.len(), 2);
+            for orientation in inward {
+                assert!(orientations.contains(&Some(orientation)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_target_mirror_may_face_out_unless_needed() {
+        // cell 1 is on the bottom edge; facing south points the target out
+        let mut node = SolverNode {
+            targets: 1,
+            ..Default::default()
+        };
+        node.cells[1] = Some(Token::target(None, false));
+        node.cells[21] = Some(Token::target(Some(Orientation::South), false));
+        assert_eq!(
+            node.orientation_iter(&TokenType::TargetMirror, 1),
+            vec![0, 1, 2, 3]
+        );
+
+        // the target in cell 21 can't be lit facing out, so the target in cell 1 is needed
+        node.cells[21] = Some(Token::target(Some(Orientation::North), false));
+        assert_eq!(
+            node.orientation_iter(&TokenType::TargetMirror, 1),
+            vec![0, 1, 3]
+        );
+    }
+
+    #[test]
+    fn test_must_light_corner_target_mirror() {
+        // in the corner, a target which must be lit can only face into the board
+        for (cell_index, inward) in [
+            (0, vec![0, 1]),
+            (4, vec![0, 3]),
+            (20, vec![1, 2]),
+            (24, vec![2, 3]),
+        ] {
+            let mut node = SolverNode::default();
+            node.cells[cell_index] = Some(Token::target(None, true));
+            assert_eq!(
+                node.orientation_iter(&TokenType::TargetMirror, cell_index),
+                inward
+            );
+        }
+
+        // the back of the target mirror in cell 5 faces cell 0, so no beam comes from the north
+        let mut node = SolverNode::default();
+        node.cells[0] = Some(Token::target(None, true));
+        node.cells[5] = Some(Token::target(Some(Orientation::East), false));
+        assert_eq!(node.orientation_iter(&TokenType::TargetMirror, 0), vec![1]);
+
+        // past the cell blocker, the checkpoint's wall faces cell 0 too, so it can't be lit at all
+        node.cells[1] = Some(Token::blocker());
+        node.cells[2] = Some(Token::checkpoint(Some(Orientation::North)));
+        assert!(node
+            .orientation_iter(&TokenType::TargetMirror, 0)
+            .is_empty());
+
+        // a target which needn't be lit may still face anywhere
+        node.cells[0] = Some(Token::target(None, false));
+        assert_eq!(
+            node.orientation_iter(&TokenType::TargetMirror, 0),
+            vec![0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_shuffled_tokens_to_be_added_keep_orientation() {
+        let mut node = SolverNode {
+            tokens_to_be_added: vec![
+                Token::splitter(Some(Orientation::East)),
+                Token::splitter(None),
+                Token::splitter(None),
+            ],
+            ..Default::default()
+        };
+        let branches = node.generate_shuffled_tokens_to_be_added_branches();
+        // the oriented splitter can go first, second, or last
+        assert_eq!(branches.len(), 3);
+        for branch in branches.iter() {
+            assert_eq!(
+                branch
+                    .tokens_to_be_added_shuffled
+                    .iter()
+                    .filter(|token| token.orientation() == Some(&Orientation::East))
+                    .count(),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_cannot_face_back_of_target_mirror() {
+        let mut node = SolverNode::default();
+        node.cells[6] = Some(Token::checkpoint(None));
+        node.cells[7] = Some(Token::target(Some(Orientation::South), false));
+        assert_eq!(node.orientation_iter(&TokenType::Checkpoint, 6), vec![0]);
+
+        // unoriented tokens might accept the laser
+        node.cells[7] = Some(Token::target(None, false));
+        assert_eq!(node.orientation_iter(&TokenType::Checkpoint, 6), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_forbidden_orientations_cell_blocker() {
+        let mut node = SolverNode::default();
+
+        // a cell blocker on a corner extends both edges for the cells next to it
+        node.cells[0] = Some(Token::blocker());
+        for i in [1, 5] {
+            assert_eq!(
+                node.forbidden_orientations(i),
+                [Some(Orientation::South), Some(Orientation::West)]
+            );
+        }
+
+        // a cell blocker on a non-corner edge extends the edge to the cell behind it
+        node.cells[0] = None;
+        node.cells[22] = Some(Token::blocker());
+        assert_eq!(
+            node.forbidden_orientations(17),
+            [Some(Orientation::North), None]
+        );
+        assert_eq!(
+            node.forbidden_orientations(21),
+            [Some(Orientation::North), None]
+        );
+    }
+
+    #[test]
+    fn test_orientation_is_legal() {
+        let mut node = SolverNode::default();
+        // cell 2 is on the bottom edge
+        node.cells[2] = Some(Token::laser(None));
+        assert!(!node.orientation_is_legal(2, &Orientation::South));
+        assert!(node.orientation_is_legal(2, &Orientation::North));
+
+        node.cells[2] = Some(Token::checkpoint(None));
+        assert!(!node.orientation_is_legal(2, &Orientation::North));
+        assert!(node.orientation_is_legal(2, &Orientation::East));
+
+        // only a target which must be lit can't face out
+        node.cells[2] = Some(Token::target(None, false));
+        assert!(node.orientation_is_legal(2, &Orientation::South));
+        node.cells[2] = Some(Token::target(None, true));
+        assert!(!node.orientation_is_legal(2, &Orientation::South));
+
+        node.cells[2] = Some(Token::splitter(None));
+        assert!(node.orientation_is_legal(2, &Orientation::South));
+    }
 }
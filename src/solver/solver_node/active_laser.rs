@@ -35,7 +35,7 @@ impl ActiveLaser {
             }
             // if we're not on the left column, decrement by 1
             Orientation::West => {
-                if self.cell_index % 5 == 0 {
+                if self.cell_index.is_multiple_of(5) {
                     None
                 } else {
                     Some(self.cell_index - 1)
@@ -1,8 +1,9 @@
 use crate::solver::orientation::Orientation;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Token {
     type_: TokenType,
     pub orientation: Option<Orientation>,
@@ -11,7 +12,7 @@ pub struct Token {
     must_light: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum LaserTokenInteractionResult {
     // The laser interacts and is re-emitted
     OutboundLaser(Orientation),
@@ -23,6 +24,65 @@ pub enum LaserTokenInteractionResult {
     NoOutboundLaser { valid: bool },
 }
 
+/// Face: what a side of a token does to a laser hitting it, with the token in the reference
+/// orientation (North)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Face {
+    // the laser leaves in this direction
+    Reflect(Orientation),
+    // the laser carries on, and is also reflected in this direction (the beam splitter)
+    Split(Orientation),
+    // the laser stops here, as it should: a target being lit, or the laser shining back into its
+    // own source
+    Absorb,
+    // the laser carries on in the same direction
+    Pass,
+    // the laser stops here, and the puzzle fails, e.g. the back of a target mirror
+    Block,
+    // the laser has come back into a wall side of the laser token
+    Invalid,
+}
+
+/// MirrorRules: how a double mirror redirects a laser, as a lookup table, so alternate rule sets can be
+/// tried. Each entry is the outbound direction for an inbound laser direction (indexed by
+/// Orientation::to_index), with the double mirror in the reference orientation (North)
+#[derive(Clone, Debug, PartialEq)]
+pub struct MirrorRules {
+    pub double_mirror: [Orientation; 4],
+}
+
+impl Default for MirrorRules {
+    fn default() -> Self {
+        Self::official()
+    }
+}
+
+impl MirrorRules {
+    // the rules of the game
+    pub fn official() -> Self {
+        Self {
+            double_mirror: [
+                Orientation::West,
+                Orientation::South,
+                Orientation::East,
+                Orientation::North,
+            ],
+        }
+    }
+
+    // house rule: the double mirror lets the laser straight through
+    pub fn transparent_double_mirror() -> Self {
+        Self {
+            double_mirror: [
+                Orientation::North,
+                Orientation::East,
+                Orientation::South,
+                Orientation::West,
+            ],
+        }
+    }
+}
+
 impl Token {
     pub fn new(type_: TokenType, orientation: Option<Orientation>, must_light: bool) -> Self {
         let must_light = if type_ == TokenType::TargetMirror {
@@ -50,6 +110,37 @@ impl Token {
         }
     }
 
+    // shorthand constructors; `new` remains the general case
+    #[allow(dead_code)]
+    pub fn laser(orientation: Option<Orientation>) -> Self {
+        Self::new(TokenType::Laser, orientation, false)
+    }
+
+    #[allow(dead_code)]
+    pub fn target(orientation: Option<Orientation>, must_light: bool) -> Self {
+        Self::new(TokenType::TargetMirror, orientation, must_light)
+    }
+
+    #[allow(dead_code)]
+    pub fn splitter(orientation: Option<Orientation>) -> Self {
+        Self::new(TokenType::BeamSplitter, orientation, false)
+    }
+
+    #[allow(dead_code)]
+    pub fn double(orientation: Option<Orientation>) -> Self {
+        Self::new(TokenType::DoubleMirror, orientation, false)
+    }
+
+    #[allow(dead_code)]
+    pub fn checkpoint(orientation: Option<Orientation>) -> Self {
+        Self::new(TokenType::Checkpoint, orientation, false)
+    }
+
+    #[allow(dead_code)]
+    pub fn blocker() -> Self {
+        Self::new(TokenType::CellBlocker, None, false)
+    }
+
     pub fn reset(&mut self) {
         self.lit = (self.type_ == TokenType::CellBlocker) || (self.type_ == TokenType::Laser);
         if self.target_lit.is_some() {
@@ -76,17 +167,37 @@ impl Token {
         self.orientation.as_ref()
     }
 
+    // symmetric tokens look the same rotated by 180 degrees; use the orientation from
+    // TokenType::orientation_range so equivalent boards compare equal
+    pub fn canonicalize_orientation(&mut self) {
+        if let Some(orientation) = &self.orientation {
+            let symmetry = self.type_.orientation_range().len();
+            self.orientation = Some(Orientation::from_index(orientation.to_index() % symmetry));
+        }
+    }
+
     pub fn outbound_lasers_given_inbound_laser_direction(
         &mut self,
         laser_inbound_orientation: &Orientation,
+    ) -> [LaserTokenInteractionResult; 2] {
+        self.outbound_lasers_with_rules(laser_inbound_orientation, &MirrorRules::official())
+    }
+
+    pub fn outbound_lasers_with_rules(
+        &mut self,
+        laser_inbound_orientation: &Orientation,
+        mirror_rules: &MirrorRules,
     ) -> [LaserTokenInteractionResult; 2] {
         let reoriented_direction = self
             .orientation
             .as_mut()
             .expect("Called check() with tokens still not having orientation set")
             .reorient_inbound_laser(laser_inbound_orientation);
-        let reoriented_outbound_lasers =
-            self.reference_outbound_lasers_given_inbound_laser_direction(reoriented_direction);
+        let reoriented_outbound_lasers = self
+            .reference_outbound_lasers_given_inbound_laser_direction(
+                reoriented_direction,
+                mirror_rules,
+            );
         // initialize array with some defaults that we'll overwrite
         let mut outbound_lasers = [
             LaserTokenInteractionResult::NoOutboundLaser { valid: false },
@@ -109,11 +220,25 @@ impl Token {
         outbound_lasers
     }
 
+    // true if a laser travelling in `laser_inbound_orientation` can't validly hit this token,
+    // e.g. it hits the back of a target mirror. doesn't mark this token as lit
+    pub fn rejects_inbound_laser(&self, laser_inbound_orientation: &Orientation) -> bool {
+        matches!(
+            self.clone()
+                .outbound_lasers_given_inbound_laser_direction(laser_inbound_orientation),
+            [
+                LaserTokenInteractionResult::NoOutboundLaser { valid: false },
+                _
+            ]
+        )
+    }
+
     // uses reference orientation for each piece to calculate its interaction with an inbound laser
     // also marks the pieces as lit
     fn reference_outbound_lasers_given_inbound_laser_direction(
         &mut self,
         laser_inbound_orientation: Orientation,
+        mirror_rules: &MirrorRules,
     ) -> [LaserTokenInteractionResult; 2] {
         type R = LaserTokenInteractionResult;
         const NONE_VALID: R = R::NoOutboundLaser { valid: true };
@@ -133,6 +258,8 @@ impl Token {
                     self.lit = true;
                     [R::OutboundLaser(laser_inbound_orientation), NONE_VALID]
                 }
+                // the laser hit a blocking face; the checkpoint stays unlit unless another
+                // laser passes through it, so Checker::all_tokens_lit fails
                 Orientation::West | Orientation::East => [NONE_INVALID, NONE_INVALID],
             },
 
@@ -151,12 +278,12 @@ impl Token {
 
             TokenType::DoubleMirror => {
                 self.lit = true;
-                match laser_inbound_orientation {
-                    Orientation::North => [R::OutboundLaser(Orientation::West), NONE_VALID],
-                    Orientation::West => [R::OutboundLaser(Orientation::North), NONE_VALID],
-                    Orientation::South => [R::OutboundLaser(Orientation::East), NONE_VALID],
-                    Orientation::East => [R::OutboundLaser(Orientation::South), NONE_VALID],
-                }
+                [
+                    R::OutboundLaser(
+                        mirror_rules.double_mirror[laser_inbound_orientation.to_index()].clone(),
+                    ),
+                    NONE_VALID,
+                ]
             }
 
             TokenType::CellBlocker => [R::OutboundLaser(laser_inbound_orientation), NONE_VALID],
@@ -188,6 +315,40 @@ impl Token {
         }
     }
 
+    /// What each side of this token does to a laser, under the official rules, with the token in the
+    /// reference orientation (its own orientation is ignored). Indexed by the direction the laser
+    /// travels (Orientation::to_index), so a laser travelling North hits the South side
+    #[allow(dead_code)]
+    pub fn faces(&self) -> [Face; 4] {
+        self.faces_with_rules(&MirrorRules::official())
+    }
+
+    /// Like .faces(), with the double mirror following `mirror_rules`
+    #[allow(dead_code)]
+    pub fn faces_with_rules(&self, mirror_rules: &MirrorRules) -> [Face; 4] {
+        type R = LaserTokenInteractionResult;
+        core::array::from_fn(|inbound_index| {
+            let inbound = Orientation::from_index(inbound_index);
+            // a copy, since the interaction marks the token lit
+            let outbound = self
+                .clone()
+                .reference_outbound_lasers_given_inbound_laser_direction(
+                    inbound.clone(),
+                    mirror_rules,
+                );
+            match outbound {
+                [R::OutboundLaser(reflected), R::OutboundLaser(_)] => Face::Split(reflected),
+                [R::OutboundLaser(outbound), _] if outbound == inbound => Face::Pass,
+                [R::OutboundLaser(outbound), _] => Face::Reflect(outbound),
+                [R::NoOutboundLaser { valid: true }, _] => Face::Absorb,
+                [R::NoOutboundLaser { valid: false }, _] if self.type_ == TokenType::Laser => {
+                    Face::Invalid
+                }
+                [R::NoOutboundLaser { valid: false }, _] => Face::Block,
+            }
+        })
+    }
+
     pub fn toggle_must_light(&mut self) {
         if self.type_ == TokenType::TargetMirror {
             self.must_light = !self.must_light;
@@ -195,6 +356,24 @@ impl Token {
     }
 }
 
+// e.g. "Target Mirror (North, must light, lit)"
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (", self.type_)?;
+        match &self.orientation {
+            Some(orientation) => write!(f, "{:?}", orientation)?,
+            None => write!(f, "unoriented")?,
+        }
+        if self.must_light {
+            write!(f, ", must light")?;
+        }
+        if self.lit {
+            write!(f, ", lit")?;
+        }
+        write!(f, ")")
+    }
+}
+
 #[derive(PartialEq, Copy, Clone, Eq, Hash, Debug, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TokenType {
     Laser,
@@ -218,6 +397,20 @@ impl TokenType {
     }
 }
 
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TokenType::Laser => "Laser",
+            TokenType::TargetMirror => "Target Mirror",
+            TokenType::BeamSplitter => "Beam Splitter",
+            TokenType::DoubleMirror => "Double Mirror",
+            TokenType::Checkpoint => "Checkpoint",
+            TokenType::CellBlocker => "Cell Blocker",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 lazy_static! {
     pub static ref TOKEN_TYPES: [TokenType; 6] = [
         TokenType::Laser,
@@ -228,3 +421,202 @@ lazy_static! {
         TokenType::CellBlocker,
     ];
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // each token's interaction written out in the absolute frame, without reorienting, as an oracle for
+    // the reference-frame transform in outbound_lasers_given_inbound_laser_direction
+    fn absolute_frame_outbound(
+        type_: &TokenType,
+        orientation: &Orientation,
+        inbound: &Orientation,
+    ) -> [LaserTokenInteractionResult; 2] {
+        use Orientation::{East as E, North as N, South as S, West as W};
+        use TokenType::*;
+        type R = LaserTokenInteractionResult;
+        const VALID: R = R::NoOutboundLaser { valid: true };
+        const INVALID: R = R::NoOutboundLaser { valid: false };
+        let out = |orientation: Orientation| [R::OutboundLaser(orientation), VALID];
+        // a double mirror or beam splitter facing N or S is a `\` mirror; facing E or W it's a `/` mirror
+        let reflect = |orientation: &Orientation| match (orientation, inbound) {
+            (N | S, N) => W,
+            (N | S, W) => N,
+            (N | S, S) => E,
+            (N | S, E) => S,
+            (E | W, N) => E,
+            (E | W, E) => N,
+            (E | W, S) => W,
+            (E | W, W) => S,
+        };
+
+        match (type_, orientation, inbound) {
+            // the laser only accepts a beam coming straight back into it
+            (Laser, N, S) | (Laser, E, W) | (Laser, S, N) | (Laser, W, E) => [VALID, VALID],
+            (Laser, _, _) => [INVALID, INVALID],
+
+            // the target faces `orientation`; a beam entering through the side a quarter turn clockwise of
+            // it hits the back of the token
+            (TargetMirror, N, N) => out(W),
+            (TargetMirror, N, E) => out(S),
+            (TargetMirror, N, S) => [VALID, VALID],
+            (TargetMirror, N, W) => [INVALID, INVALID],
+            (TargetMirror, E, N) => [INVALID, INVALID],
+            (TargetMirror, E, E) => out(N),
+            (TargetMirror, E, S) => out(W),
+            (TargetMirror, E, W) => [VALID, VALID],
+            (TargetMirror, S, N) => [VALID, VALID],
+            (TargetMirror, S, E) => [INVALID, INVALID],
+            (TargetMirror, S, S) => out(E),
+            (TargetMirror, S, W) => out(N),
+            (TargetMirror, W, N) => out(E),
+            (TargetMirror, W, E) => [VALID, VALID],
+            (TargetMirror, W, S) => [INVALID, INVALID],
+            (TargetMirror, W, W) => out(S),
+
+            (DoubleMirror, _, _) => out(reflect(orientation)),
+
+            (BeamSplitter, _, _) => [
+                R::OutboundLaser(reflect(orientation)),
+                R::OutboundLaser(inbound.clone()),
+            ],
+
+            // the checkpoint passes beams along its axis and blocks the others
+            (Checkpoint, N | S, N | S) | (Checkpoint, E | W, E | W) => out(inbound.clone()),
+            (Checkpoint, _, _) => [INVALID, INVALID],
+
+            (CellBlocker, _, _) => out(inbound.clone()),
+        }
+    }
+
+    #[test]
+    fn test_outbound_lasers_match_absolute_frame() {
+        for type_ in TOKEN_TYPES.iter() {
+            for orientation in (0..4).map(Orientation::from_index) {
+                for inbound in (0..4).map(Orientation::from_index) {
+                    let mut token = Token::new(*type_, None, false);
+                    token.orientation = Some(orientation.clone());
+                    let expected = absolute_frame_outbound(type_, &orientation, &inbound);
+                    assert_eq!(
+                        token.outbound_lasers_given_inbound_laser_direction(&inbound),
+                        expected,
+                        "{:?} facing {:?}, laser travelling {:?}",
+                        type_,
+                        orientation,
+                        inbound
+                    );
+                    // a target mirror hit on its target face absorbs the beam validly
+                    if type_ == &TokenType::TargetMirror {
+                        let target_hit = expected.iter().all(|result| {
+                            result == &LaserTokenInteractionResult::NoOutboundLaser { valid: true }
+                        });
+                        assert_eq!(
+                            token.target_lit(),
+                            Some(target_hit),
+                            "{:?} facing {:?}, laser travelling {:?}",
+                            type_,
+                            orientation,
+                            inbound
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mirror_rules() {
+        let mut token = Token::double(Some(Orientation::North));
+        assert!(matches!(
+            token.outbound_lasers_given_inbound_laser_direction(&Orientation::West),
+            [
+                LaserTokenInteractionResult::OutboundLaser(Orientation::North),
+                _
+            ]
+        ));
+        assert!(matches!(
+            token.outbound_lasers_with_rules(
+                &Orientation::West,
+                &MirrorRules::transparent_double_mirror()
+            ),
+            [
+                LaserTokenInteractionResult::OutboundLaser(Orientation::West),
+                _
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_faces() {
+        use Orientation::{East as E, North as N, South as S, West as W};
+        // indexed by the direction the laser travels: N, E, S, W
+        assert_eq!(
+            Token::laser(None).faces(),
+            [Face::Invalid, Face::Invalid, Face::Absorb, Face::Invalid]
+        );
+        assert_eq!(
+            Token::target(None, false).faces(),
+            [
+                Face::Reflect(W),
+                Face::Reflect(S),
+                Face::Absorb,
+                Face::Block
+            ]
+        );
+        assert_eq!(
+            Token::splitter(None).faces(),
+            [
+                Face::Split(W),
+                Face::Split(S),
+                Face::Split(E),
+                Face::Split(N)
+            ]
+        );
+        assert_eq!(
+            Token::double(None).faces(),
+            [
+                Face::Reflect(W),
+                Face::Reflect(S),
+                Face::Reflect(E),
+                Face::Reflect(N)
+            ]
+        );
+        assert_eq!(
+            Token::checkpoint(None).faces(),
+            [Face::Pass, Face::Block, Face::Pass, Face::Block]
+        );
+        assert_eq!(
+            Token::blocker().faces(),
+            [Face::Pass, Face::Pass, Face::Pass, Face::Pass]
+        );
+
+        // the token's own orientation doesn't matter
+        assert_eq!(
+            Token::target(Some(E), true).faces(),
+            Token::target(None, false).faces()
+        );
+        // under the house rule, the double mirror lets the laser through
+        assert_eq!(
+            Token::double(None).faces_with_rules(&MirrorRules::transparent_double_mirror()),
+            [Face::Pass, Face::Pass, Face::Pass, Face::Pass]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(TokenType::BeamSplitter.to_string(), "Beam Splitter");
+        assert_eq!(
+            Token::splitter(None).to_string(),
+            "Beam Splitter (unoriented)"
+        );
+        assert_eq!(
+            Token::target(Some(Orientation::North), true).to_string(),
+            "Target Mirror (North, must light)"
+        );
+        assert_eq!(
+            Token::laser(Some(Orientation::East)).to_string(),
+            "Laser (East, lit)"
+        );
+    }
+}